@@ -0,0 +1,188 @@
+use crate::config;
+use crate::create::{confirm, dir_size};
+use crate::delete::{Repository, get_repositories, remove_repository};
+use crate::output::human_size;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn filter_group<'a>(repositories: &'a [Repository], group: &str) -> Vec<&'a Repository> {
+    repositories
+        .iter()
+        .filter(|repo| repo.metadata.group.as_deref() == Some(group))
+        .collect()
+}
+
+pub fn parse_age(value: &str) -> Result<TimeDelta, String> {
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in '{value}' (use m, h, d or w)"))?;
+    let (amount, unit) = value.split_at(unit_start);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid number in '{value}'"))?;
+
+    let age = match unit {
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => return Err(format!("unknown unit '{unit}' (use m, h, d or w)")),
+    };
+    age.ok_or_else(|| format!("'{value}' is too long"))
+}
+
+fn is_older_than(repo: &Repository, age: TimeDelta, now: DateTime<Utc>) -> bool {
+    now - repo.metadata.created_at > age
+}
+
+fn last_commit_date(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("log")
+        .arg("-1")
+        .arg("--format=%cd")
+        .arg("--date=short")
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Prints each copy with its size and last commit, and returns the total size
+fn print_preview(repo_sync_path: &Path, targets: &[&Repository]) -> u64 {
+    let mut total = 0;
+    for repo in targets {
+        let dir = repo_sync_path.join(&repo.directory);
+        let size = if repo.metadata.branch_only {
+            0
+        } else {
+            dir_size(&dir)
+        };
+        total += size;
+        println!(
+            "  {}\t{}\t{}\tlast commit {}",
+            repo.metadata.created_at.format("%Y-%m-%d %H:%M:%S"),
+            repo.metadata.branch,
+            human_size(size),
+            last_commit_date(&dir).unwrap_or_else(|| "-".to_string())
+        );
+    }
+    total
+}
+
+pub fn clean_repos(
+    group: Option<&str>,
+    older_than: Option<TimeDelta>,
+    preview: bool,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+    let mut targets: Vec<&Repository> = match group {
+        Some(group) => filter_group(&repositories, group),
+        None => repositories.iter().collect(),
+    };
+    if let Some(age) = older_than {
+        let now = Utc::now();
+        targets.retain(|repo| is_older_than(repo, age, now));
+    }
+
+    if targets.is_empty() {
+        println!("No matching repositories found.");
+        return Ok(());
+    }
+
+    println!("Repositories to delete:");
+    if preview {
+        let total = print_preview(&PathBuf::from(&config.settings.repo_sync_path), &targets);
+        println!("Total reclaimable: {}", human_size(total));
+    } else {
+        for repo in &targets {
+            println!(
+                "  {}\t{}",
+                repo.metadata.created_at.format("%Y-%m-%d %H:%M:%S"),
+                repo.metadata.branch
+            );
+        }
+    }
+    println!();
+
+    if !yes && !confirm(&format!("Delete these {} repositories?", targets.len()))? {
+        println!("Deletion cancelled.");
+        return Ok(());
+    }
+
+    for repo in targets {
+        remove_repository(&config, repo, false, false, yes)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create::RepositoryMetadata;
+
+    fn repository(branch: &str, group: Option<&str>) -> Repository {
+        Repository {
+            metadata: RepositoryMetadata {
+                branch: branch.to_string(),
+                group: group.map(str::to_string),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_group() {
+        let repositories = vec![
+            repository("feature/a", Some("epic")),
+            repository("feature/b", None),
+            repository("feature/c", Some("epic")),
+        ];
+
+        let branches: Vec<_> = filter_group(&repositories, "epic")
+            .iter()
+            .map(|repo| repo.metadata.branch.as_str())
+            .collect();
+        assert_eq!(branches, vec!["feature/a", "feature/c"]);
+        assert!(filter_group(&repositories, "other").is_empty());
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d"), Ok(TimeDelta::days(30)));
+        assert_eq!(parse_age("12h"), Ok(TimeDelta::hours(12)));
+        assert_eq!(parse_age("2w"), Ok(TimeDelta::weeks(2)));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+        // Out of TimeDelta's range instead of panicking
+        assert!(parse_age("9999999999999w").is_err());
+        assert!(parse_age("99999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_is_older_than() {
+        let now = Utc::now();
+        let repo = Repository {
+            metadata: RepositoryMetadata {
+                branch: "feature/old".to_string(),
+                created_at: now - TimeDelta::days(40),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(is_older_than(&repo, TimeDelta::days(30), now));
+        assert!(!is_older_than(&repo, TimeDelta::days(60), now));
+    }
+}
@@ -1,31 +1,265 @@
+use crate::output;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub settings: Settings,
-    pub branch_aliases: HashMap<String, String>,
+    pub branch_aliases: HashMap<String, BranchAlias>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Settings {
     pub repo_sync_path: String,
     pub tmux_window_init_commands: String,
     pub rsync_excludes: Vec<String>,
+    #[serde(default = "default_name_separator")]
+    pub name_separator: String,
+    #[serde(default)]
+    pub link_paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rsync_timeout: Option<u64>,
+    #[serde(default)]
+    pub dirty_tree_policy: DirtyTreePolicy,
+    #[serde(default)]
+    pub current_branch_policy: CurrentBranchPolicy,
+    #[serde(default = "default_rsync_preserve_times")]
+    pub rsync_preserve_times: bool,
+    #[serde(default = "default_rsync_flags")]
+    pub rsync_flags: Vec<String>,
+    #[serde(default = "default_rsync_preserve_perms")]
+    pub rsync_preserve_perms: bool,
+    #[serde(default = "default_rsync_preserve_owner")]
+    pub rsync_preserve_owner: bool,
+    #[serde(default = "default_rsync_preserve_group")]
+    pub rsync_preserve_group: bool,
+    #[serde(default = "default_show_log_on_delete")]
+    pub show_log_on_delete: bool,
+    #[serde(default)]
+    pub continue_on_tmux_error: bool,
+    #[serde(default = "default_clear_before_confirm")]
+    pub clear_before_confirm: bool,
+    #[serde(default)]
+    pub init_commands_space_prefix: bool,
+    #[serde(default)]
+    pub init_commands_template_engine: bool,
+    #[serde(default)]
+    pub delete_branch: bool,
+    #[serde(default)]
+    pub create_session_without_tty: bool,
+    #[serde(default)]
+    pub dirty_ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_large_dirs_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_attach_command: Option<String>,
+    #[serde(default = "default_check_git_state")]
+    pub check_git_state: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_window_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_renumber_windows: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_session_group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_command_timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rsync_max_file_size: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks_dir: Option<String>,
+    #[serde(default)]
+    pub delete_confirm: DeleteConfirm,
+    #[serde(default)]
+    pub use_gitignore: bool,
+    #[serde(default)]
+    pub copy_strategy: CopyStrategy,
+    #[serde(default = "default_repo_prefix_length")]
+    pub repo_prefix_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_prefix: Option<String>,
+    #[serde(default)]
+    pub tmux_mode: TmuxMode,
+    #[serde(default = "default_metadata_filename")]
+    pub metadata_filename: String,
+    #[serde(default)]
+    pub id_scheme: IdScheme,
+    #[serde(default)]
+    pub global_default_args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir_prefix_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_create_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_delete_command: Option<String>,
+}
+
+fn default_metadata_filename() -> String {
+    "{ulid}".to_string()
+}
+
+fn default_rsync_preserve_times() -> bool {
+    true
+}
+
+fn default_rsync_flags() -> Vec<String> {
+    vec!["-a".to_string()]
+}
+
+fn default_rsync_preserve_perms() -> bool {
+    true
+}
+
+fn default_rsync_preserve_owner() -> bool {
+    true
+}
+
+fn default_rsync_preserve_group() -> bool {
+    true
+}
+
+fn default_show_log_on_delete() -> bool {
+    true
+}
+
+fn default_clear_before_confirm() -> bool {
+    true
+}
+
+fn default_check_git_state() -> bool {
+    true
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DirtyTreePolicy {
+    /// Copy uncommitted changes as-is
+    #[default]
+    Copy,
+    /// Commit the copied changes as WIP on the new branch
+    Commit,
+    /// Ask for confirmation before copying
+    Warn,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrentBranchPolicy {
+    /// Refuse and suggest a different branch name
+    #[default]
+    Error,
+    /// Copy and stay on the branch without creating it
+    Stay,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IdScheme {
+    /// 26-character ULID, sortable by creation time
+    #[default]
+    Ulid,
+    /// UUIDv7, sortable by creation time
+    Uuid,
+    /// UTC creation time with a short random suffix
+    Timestamp,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyStrategy {
+    /// Copy the working tree with rsync, including uncommitted changes
+    #[default]
+    Rsync,
+    /// Check out the new branch with `git worktree add`
+    Worktree,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TmuxMode {
+    /// A window in the current session inside tmux, otherwise a new session
+    #[default]
+    Auto,
+    /// A window in the current session (a new session outside tmux)
+    Window,
+    /// A new session per copy
+    Session,
+    /// A window per copy in a session named after the repository
+    RepoSession,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteConfirm {
+    /// Answer a y/N prompt
+    #[default]
+    Prompt,
+    /// Type the branch name of the copy
+    Typed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum BranchAlias {
+    /// Prefix the alias expands to (a leading `!` runs it as a shell command)
+    Expansion(String),
+    /// Expansion with overrides for copies created through the alias
+    Detailed {
+        expansion: String,
+        /// Used instead of `tmux_window_init_commands`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        init_commands: Option<String>,
+    },
+}
+
+impl BranchAlias {
+    pub fn expansion(&self) -> &str {
+        match self {
+            BranchAlias::Expansion(expansion) | BranchAlias::Detailed { expansion, .. } => {
+                expansion
+            }
+        }
+    }
+
+    pub fn init_commands(&self) -> Option<&str> {
+        match self {
+            BranchAlias::Expansion(_) => None,
+            BranchAlias::Detailed { init_commands, .. } => init_commands.as_deref(),
+        }
+    }
+}
+
+impl From<&str> for BranchAlias {
+    fn from(expansion: &str) -> Self {
+        BranchAlias::Expansion(expansion.to_string())
+    }
+}
+
+fn default_repo_prefix_length() -> usize {
+    3
+}
+
+fn default_name_separator() -> String {
+    "-".to_string()
 }
 
 impl Default for Config {
     fn default() -> Self {
         let mut branch_aliases = HashMap::new();
-        branch_aliases.insert("@f".to_string(), "feature".to_string());
-        branch_aliases.insert("@b".to_string(), "bugfix".to_string());
+        branch_aliases.insert("@f".to_string(), "feature".into());
+        branch_aliases.insert("@b".to_string(), "bugfix".into());
         branch_aliases.insert(
             "@t".to_string(),
-            "!echo feature/$(date +%Y%m%d-%H%M%S)".to_string(),
+            "!echo feature/$(date +%Y%m%d-%H%M%S)".into(),
         );
 
         Config {
@@ -43,13 +277,53 @@ tmux select-pane -t 1
                 rsync_excludes: vec![
                     "target".to_string()
                 ],
+                name_separator: default_name_separator(),
+                link_paths: Vec::new(),
+                rsync_timeout: None,
+                dirty_tree_policy: DirtyTreePolicy::default(),
+                current_branch_policy: CurrentBranchPolicy::default(),
+                rsync_preserve_times: default_rsync_preserve_times(),
+                rsync_flags: default_rsync_flags(),
+                rsync_preserve_perms: default_rsync_preserve_perms(),
+                rsync_preserve_owner: default_rsync_preserve_owner(),
+                rsync_preserve_group: default_rsync_preserve_group(),
+                show_log_on_delete: default_show_log_on_delete(),
+                continue_on_tmux_error: false,
+                clear_before_confirm: default_clear_before_confirm(),
+                init_commands_space_prefix: false,
+                init_commands_template_engine: false,
+                delete_branch: false,
+                create_session_without_tty: false,
+                dirty_ignore: Vec::new(),
+                warn_large_dirs_mb: None,
+                on_attach_command: None,
+                check_git_state: default_check_git_state(),
+                tmux_window_index: None,
+                tmux_renumber_windows: None,
+                tmux_session_group: None,
+                tmux_command_timeout_secs: None,
+                rsync_max_file_size: None,
+                archive_dir: None,
+                hooks_dir: None,
+                delete_confirm: DeleteConfirm::default(),
+                use_gitignore: false,
+                copy_strategy: CopyStrategy::default(),
+                repo_prefix_length: default_repo_prefix_length(),
+                repo_prefix: None,
+                tmux_mode: TmuxMode::default(),
+                metadata_filename: default_metadata_filename(),
+                id_scheme: IdScheme::default(),
+                global_default_args: Vec::new(),
+                dir_prefix_template: None,
+                pre_create_command: None,
+                post_delete_command: None,
             },
             branch_aliases,
         }
     }
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
+pub fn expand_tilde(path: &str) -> PathBuf {
     if path.starts_with('~') {
         if let Some(home) = dirs::home_dir() {
             return home.join(&path[2..]);
@@ -58,18 +332,302 @@ fn expand_tilde(path: &str) -> PathBuf {
     PathBuf::from(path)
 }
 
-fn get_config_path() -> PathBuf {
-    if let Ok(config_path) = env::var("TRR_CONFIG_PATH") {
-        expand_tilde(&config_path)
-    } else {
-        dirs::home_dir()
-            .expect("Failed to get home directory")
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrrError {
+    /// The home directory could not be determined (e.g. HOME is unset)
+    NoHomeDir,
+}
+
+impl fmt::Display for TrrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrrError::NoHomeDir => write!(
+                f,
+                "Failed to get the home directory. Set HOME, or TRR_CONFIG_PATH to the config file"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TrrError {}
+
+fn resolve_config_path(
+    config_path: Option<String>,
+    home_dir: Option<PathBuf>,
+) -> Result<PathBuf, TrrError> {
+    match config_path {
+        Some(config_path) => Ok(expand_tilde(&config_path)),
+        None => Ok(home_dir
+            .ok_or(TrrError::NoHomeDir)?
             .join(".config")
             .join("trr")
-            .join("config.toml")
+            .join("config.toml")),
     }
 }
 
+pub fn get_config_path() -> Result<PathBuf, TrrError> {
+    resolve_config_path(env::var("TRR_CONFIG_PATH").ok(), dirs::home_dir())
+}
+
+fn get_profile_path(config_path: &Path, profile: &str) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(format!("{profile}.toml"))
+}
+
+fn merge_toml(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base_table), toml::Value::Table(override_table)) => {
+            for (key, value) in override_table {
+                match base_table.get_mut(&key) {
+                    Some(base_value) => merge_toml(base_value, value),
+                    None => {
+                        base_table.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn apply_host_overrides(value: &mut toml::Value, hostname: Option<&str>) {
+    let Some(toml::Value::Table(hosts)) = value.as_table_mut().and_then(|t| t.remove("hosts"))
+    else {
+        return;
+    };
+    let Some(hostname) = hostname else {
+        return;
+    };
+
+    // Match the full hostname first, then the short name before the first '.'
+    let short_name = hostname.split('.').next().unwrap_or(hostname);
+    let host_settings = hosts
+        .get(hostname)
+        .or_else(|| hosts.get(short_name))
+        .cloned();
+
+    if let Some(host_settings) = host_settings {
+        let mut overrides = toml::Table::new();
+        overrides.insert("settings".to_string(), host_settings);
+        merge_toml(value, toml::Value::Table(overrides));
+    }
+}
+
+fn known_keys() -> (Vec<String>, Vec<String>) {
+    let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+    let keys = |properties: &serde_json::Value| -> Vec<String> {
+        properties
+            .as_object()
+            .map(|properties| properties.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+
+    let mut top_level = keys(&schema["properties"]);
+    top_level.push("hosts".to_string());
+    (top_level, keys(&schema["$defs"]["Settings"]["properties"]))
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn closest_key<'a>(key: &str, candidates: &'a [String]) -> Option<&'a str> {
+    let max_distance = (key.len() / 3).max(2);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+// Finds the 1-based line and column where a key is written, either as
+// `key = ...` or as a `[key]` table header
+fn locate_key(text: &str, key: &str) -> Option<(usize, usize)> {
+    text.lines().enumerate().find_map(|(index, line)| {
+        let rest = line.trim_start().trim_start_matches('[').trim_start();
+        let column = line.len() - rest.len() + 1;
+        rest.strip_prefix(key)?
+            .trim_start()
+            .starts_with(['=', '.', ']'])
+            .then_some((index + 1, column))
+    })
+}
+
+fn check_keys(
+    text: &str,
+    value: &toml::Value,
+    section: &str,
+    known: &[String],
+) -> Result<(), String> {
+    let Some(table) = value.as_table() else {
+        return Ok(());
+    };
+
+    if let Some(key) = table.keys().find(|key| !known.contains(key)) {
+        let mut message = format!("unknown key `{key}` in {section}");
+        if let Some((line, column)) = locate_key(text, key) {
+            message.push_str(&format!(" at line {line}, column {column}"));
+        }
+        if let Some(suggestion) = closest_key(key, known) {
+            message.push_str(&format!(" (did you mean `{suggestion}`?)"));
+        }
+        return Err(message);
+    }
+    Ok(())
+}
+
+// tmux reads ':' and '.' in a target as session:window.pane delimiters, and '/' is
+// replaced with '-' in the branch part of a name, so none of them can separate the two
+fn check_name_separator(section: &toml::Value, section_name: &str) -> Result<(), String> {
+    match section
+        .get("name_separator")
+        .and_then(|value| value.as_str())
+    {
+        Some(separator) if separator.contains([':', '.', '/']) => Err(format!(
+            "name_separator in {section_name} must not contain ':', '.' or '/'"
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Parses a config or profile file and rejects keys trr does not know, so a
+// typo is reported instead of being silently ignored
+fn validate(text: &str) -> Result<toml::Value, String> {
+    let value = toml::from_str::<toml::Value>(text).map_err(|e| e.to_string())?;
+    let (top_level, settings) = known_keys();
+
+    check_keys(text, &value, "the top level", &top_level)?;
+    if let Some(section) = value.get("settings") {
+        check_keys(text, section, "[settings]", &settings)?;
+        check_name_separator(section, "[settings]")?;
+    }
+    if let Some(hosts) = value.get("hosts").and_then(|hosts| hosts.as_table()) {
+        for (host, section) in hosts {
+            check_keys(text, section, &format!("[hosts.{host}]"), &settings)?;
+            check_name_separator(section, &format!("[hosts.{host}]"))?;
+        }
+    }
+    Ok(value)
+}
+
+fn read_config_value(path: &Path) -> Result<toml::Value, Box<dyn std::error::Error>> {
+    validate(&fs::read_to_string(path)?)
+        .map_err(|e| format!("Invalid config {}: {e}", path.display()).into())
+}
+
+fn load_with_profile(
+    config_path: &Path,
+    profile: Option<&str>,
+) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut value = if config_path.exists() {
+        read_config_value(config_path)?
+    } else {
+        toml::Value::try_from(Config::default())?
+    };
+
+    if let Some(profile) = profile {
+        let profile_path = get_profile_path(config_path, profile);
+        if !profile_path.exists() {
+            return Err(format!("Profile config not found: {}", profile_path.display()).into());
+        }
+        let profile_value = read_config_value(&profile_path)?;
+        merge_toml(&mut value, profile_value);
+    }
+
+    let hostname = hostname::get()
+        .ok()
+        .map(|name| name.to_string_lossy().to_string());
+    apply_host_overrides(&mut value, hostname.as_deref());
+
+    value
+        .try_into()
+        .map_err(|e: toml::de::Error| format!("Invalid config: {}", e.message()).into())
+}
+
+fn write_default_config(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let default_config = Config::default();
+    let toml_string = toml::to_string_pretty(&default_config)?;
+    fs::write(config_path, toml_string)?;
+    Ok(())
+}
+
+fn auto_init_enabled() -> bool {
+    env::var("TRR_AUTO_INIT").is_ok_and(|value| value == "1")
+}
+
+pub fn load_config_file(config_path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+    if auto_init_enabled() && !config_path.exists() {
+        write_default_config(config_path)?;
+        eprintln!(
+            "Created default config file at: {} (edit it with `trr config`)",
+            config_path.display()
+        );
+    }
+
+    let profile = env::var("TRR_PROFILE").ok().filter(|p| !p.is_empty());
+    load_with_profile(config_path, profile.as_deref())
+}
+
+pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+    load_config_file(&get_config_path()?)
+}
+
+// Rewrites the base config file, so comments in it are not preserved
+pub fn add_rsync_excludes(excludes: &[String]) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let config_path = get_config_path()?;
+    if !config_path.exists() {
+        write_default_config(&config_path)?;
+    }
+
+    let mut value = toml::from_str::<toml::Value>(&fs::read_to_string(&config_path)?)?;
+    append_rsync_excludes(&mut value, excludes)?;
+    fs::write(&config_path, toml::to_string_pretty(&value)?)?;
+    Ok(config_path)
+}
+
+fn append_rsync_excludes(
+    value: &mut toml::Value,
+    excludes: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = value
+        .as_table_mut()
+        .ok_or("Config file is not a table")?
+        .entry("settings")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or("[settings] is not a table")?;
+    let rsync_excludes = settings
+        .entry("rsync_excludes")
+        .or_insert_with(|| toml::Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or("settings.rsync_excludes is not an array")?;
+
+    for exclude in excludes {
+        let exclude = toml::Value::String(exclude.clone());
+        if !rsync_excludes.contains(&exclude) {
+            rsync_excludes.push(exclude);
+        }
+    }
+    Ok(())
+}
+
 fn get_editor() -> Option<String> {
     env::var("TRR_EDITOR")
         .or_else(|_| env::var("EDITOR"))
@@ -77,17 +635,413 @@ fn get_editor() -> Option<String> {
         .ok()
 }
 
-pub fn init_config() -> Result<(), Box<dyn std::error::Error>> {
-    let config_path = get_config_path();
+// Settings of the commented template in order: key, explanatory note, and an
+// example for settings without a default. Required settings are left active
+const TEMPLATE_SETTINGS: &[(&str, &str, Option<&str>)] = &[
+    (
+        "repo_sync_path",
+        "Directory where repositories are synced (relative to current directory)",
+        None,
+    ),
+    (
+        "tmux_window_init_commands",
+        "Commands to run after creating a new tmux window. @@args, @@branch and @@dir are\n\
+         replaced with the arguments after --, the branch name, and the copy's path",
+        None,
+    ),
+    (
+        "rsync_excludes",
+        "Directories/files to exclude when copying (repo_sync_path is always excluded)",
+        None,
+    ),
+    (
+        "global_default_args",
+        "Args placed before the ones passed after -- in every `trr create`",
+        None,
+    ),
+    (
+        "dir_prefix_template",
+        "Prefix for copy directory names; {date} (YYYYMMDD) and {ulid} are replaced",
+        Some("\"{date}-\""),
+    ),
+    (
+        "tmux_mode",
+        "Where each copy's shell is opened: \"auto\", \"window\", \"session\", or \"repo-session\"",
+        None,
+    ),
+    (
+        "metadata_filename",
+        "Metadata file name in <repo_sync_path>/.trr-sys: \"{ulid}\" or \"{directory}\"",
+        None,
+    ),
+    (
+        "id_scheme",
+        "ID given to each copy (the {ulid} placeholders): \"ulid\", \"uuid\", or \"timestamp\"",
+        None,
+    ),
+    (
+        "name_separator",
+        "Separator between the repo prefix and branch in tmux names (must not contain \":\", \".\" or \"/\")",
+        None,
+    ),
+    (
+        "repo_prefix_length",
+        "Characters of the repository name used as the tmux name prefix (0 = full name)",
+        None,
+    ),
+    (
+        "repo_prefix",
+        "Use this tmux name prefix verbatim instead of deriving it from the repository name",
+        Some("\"mono\""),
+    ),
+    (
+        "use_gitignore",
+        "Also exclude the patterns in the repository's top-level .gitignore",
+        None,
+    ),
+    (
+        "link_paths",
+        "Paths symlinked back to the source instead of copied (with --link-config)",
+        None,
+    ),
+    (
+        "copy_strategy",
+        "How the copy is made: \"rsync\" or \"worktree\" (git worktree add)",
+        None,
+    ),
+    (
+        "dirty_tree_policy",
+        "Uncommitted changes in the source: \"copy\", \"commit\" (as WIP), or \"warn\"",
+        None,
+    ),
+    (
+        "current_branch_policy",
+        "Creating a copy of the branch checked out in the source: \"error\" or \"stay\"",
+        None,
+    ),
+    (
+        "dirty_ignore",
+        "Git pathspecs ignored when deciding whether a working tree is dirty",
+        None,
+    ),
+    (
+        "rsync_preserve_times",
+        "Preserve source modification times in the copy (false passes --no-times)",
+        None,
+    ),
+    (
+        "rsync_flags",
+        "Flags passed to rsync in place of the default -a, e.g. [\"-a\", \"-L\", \"-H\"]",
+        None,
+    ),
+    (
+        "rsync_preserve_perms",
+        "Preserve file permissions in the copy (false drops -p from -a)",
+        None,
+    ),
+    (
+        "rsync_preserve_owner",
+        "Preserve file owners in the copy (false drops -o from -a)",
+        None,
+    ),
+    (
+        "rsync_preserve_group",
+        "Preserve file groups in the copy (false drops -g from -a)",
+        None,
+    ),
+    (
+        "show_log_on_delete",
+        "Show recent commits and uncommitted changes before confirming deletion",
+        None,
+    ),
+    (
+        "continue_on_tmux_error",
+        "Keep the copy and report tmux errors at the end instead of failing",
+        None,
+    ),
+    (
+        "delete_confirm",
+        "How deletion is confirmed: \"prompt\" (y/N) or \"typed\" (type the branch name)",
+        None,
+    ),
+    (
+        "clear_before_confirm",
+        "Clear the screen before the delete confirmation prompt",
+        None,
+    ),
+    (
+        "init_commands_space_prefix",
+        "Prefix init commands with a space to keep them out of shell history",
+        None,
+    ),
+    (
+        "init_commands_template_engine",
+        "Render tmux_window_init_commands as a template before running it",
+        None,
+    ),
+    (
+        "delete_branch",
+        "Delete the git branch of --branch-only and worktree copies when deleting them",
+        None,
+    ),
+    (
+        "create_session_without_tty",
+        "Create the tmux session detached when running outside tmux without a TTY",
+        None,
+    ),
+    (
+        "warn_large_dirs_mb",
+        "Warn about directories larger than this many MB that aren't excluded",
+        Some("500"),
+    ),
+    (
+        "on_attach_command",
+        "Command sent to the active pane every time trr attaches to a copy",
+        Some("\"git status -sb\""),
+    ),
+    (
+        "check_git_state",
+        "Refuse to copy while a rebase, merge, or similar is in progress in the source",
+        None,
+    ),
+    (
+        "tmux_window_index",
+        "Index for new windows created inside tmux; windows from that index on move up when it is taken",
+        Some("5"),
+    ),
+    (
+        "tmux_renumber_windows",
+        "Whether the session renumbers windows (tmux renumber-windows)",
+        Some("true"),
+    ),
+    (
+        "tmux_session_group",
+        "tmux session group that new copy sessions join (new-session -t)",
+        Some("\"dashboard\""),
+    ),
+    (
+        "tmux_command_timeout_secs",
+        "Abort a tmux command that hasn't returned after this many seconds",
+        Some("10"),
+    ),
+    (
+        "rsync_max_file_size",
+        "Skip files larger than this when copying (rsync --max-size)",
+        Some("\"100M\""),
+    ),
+    (
+        "archive_dir",
+        "Directory containing archived copies for `trr restore`",
+        Some("\"~/.trr-archive\""),
+    ),
+    (
+        "hooks_dir",
+        "Directory of pre-create/post-create/pre-delete/post-delete hook scripts",
+        Some("\"~/.config/trr/hooks\""),
+    ),
+    (
+        "pre_create_command",
+        "Shell command run in the source before copying; @@branch, @@dir and @@args are replaced. A failure aborts the creation",
+        Some("\"npm install --package-lock-only\""),
+    ),
+    (
+        "post_delete_command",
+        "Shell command run after a copy is deleted; @@branch and @@dir are replaced",
+        Some("\"docker volume rm -f app-@@branch\""),
+    ),
+    (
+        "rsync_timeout",
+        "Abort rsync when no data is transferred for this many seconds",
+        Some("300"),
+    ),
+];
+
+const TEMPLATE_REQUIRED: &[&str] = &[
+    "repo_sync_path",
+    "tmux_window_init_commands",
+    "rsync_excludes",
+];
+
+fn render_template_entry(key: &str, value: &toml::Value) -> Result<String, toml::ser::Error> {
+    let mut table = toml::Table::new();
+    table.insert(key.to_string(), value.clone());
+    Ok(toml::to_string_pretty(&table)?.trim_end().to_string())
+}
+
+fn comment_out(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("# {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn config_template() -> Result<String, Box<dyn std::error::Error>> {
+    let defaults = toml::Value::try_from(Config::default())?;
+    let mut template = String::from("[settings]\n");
+
+    for (key, note, example) in TEMPLATE_SETTINGS {
+        let entry = match (defaults["settings"].get(key), example) {
+            (Some(value), _) => render_template_entry(key, value)?,
+            (None, Some(example)) => format!("{key} = {example}"),
+            (None, None) => return Err(format!("No default or example for {key}").into()),
+        };
+        template.push_str(&comment_out(note));
+        template.push('\n');
+        if TEMPLATE_REQUIRED.contains(key) {
+            template.push_str(&entry);
+        } else {
+            template.push_str(&comment_out(&entry));
+        }
+        template.push_str("\n\n");
+    }
+
+    template.push_str("[branch_aliases]\n");
+    template.push_str(
+        "# Prefixes expanded in branch names; a leading ! runs the expansion as a shell command.\n\
+         # A table can also override the init commands, e.g.\n\
+         # \"@b\" = { expansion = \"bugfix\", init_commands = \"tmux split-window -h\" }\n",
+    );
+    let mut aliases: Vec<_> = defaults["branch_aliases"]
+        .as_table()
+        .into_iter()
+        .flatten()
+        .collect();
+    aliases.sort_by_key(|(alias, _)| alias.as_str());
+    for (alias, expansion) in aliases {
+        template.push_str(&render_template_entry(alias, expansion)?);
+        template.push('\n');
+    }
+    Ok(template)
+}
+
+pub fn print_template() -> Result<(), Box<dyn std::error::Error>> {
+    print!("{}", config_template()?);
+    Ok(())
+}
+
+pub fn print_schema(json_pretty: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let schema = schemars::schema_for!(Config);
+    println!("{}", output::to_json(&schema, json_pretty)?);
+    Ok(())
+}
+
+fn diff_toml(base: &toml::Value, merged: &toml::Value, prefix: &str, changes: &mut Vec<String>) {
+    let (Some(base_table), Some(merged_table)) = (base.as_table(), merged.as_table()) else {
+        if base != merged {
+            changes.push(format!("~ {prefix} = {merged} (was {base})"));
+        }
+        return;
+    };
+
+    for (key, merged_value) in merged_table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match base_table.get(key) {
+            Some(base_value) => diff_toml(base_value, merged_value, &path, changes),
+            None => changes.push(format!("+ {path} = {merged_value}")),
+        }
+    }
+}
+
+// Only the tables trr reads are taken from the merge file
+fn merge_config_values(
+    base: &mut toml::Value,
+    overrides: toml::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let toml::Value::Table(mut overrides) = overrides else {
+        return Err("The merge file must be a TOML table".into());
+    };
+    overrides.retain(|key, _| key == "settings" || key == "branch_aliases");
+    merge_toml(base, toml::Value::Table(overrides));
+    Ok(())
+}
+
+// [hosts.*] tables are not part of Config; they are stripped before the type check
+// the same way loading does
+fn check_merged_config(merged: &toml::Value) -> Result<(), Box<dyn std::error::Error>> {
+    let mut checked = merged.clone();
+    apply_host_overrides(&mut checked, None);
+    checked
+        .try_into::<Config>()
+        .map_err(|e| format!("The merged config is invalid: {}", e.message()))?;
+    Ok(())
+}
+
+pub fn merge_config(merge_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = get_config_path()?;
+    let base = if config_path.exists() {
+        toml::from_str::<toml::Value>(&fs::read_to_string(&config_path)?)
+            .map_err(|e| format!("Failed to parse {}: {e}", config_path.display()))?
+    } else {
+        toml::Value::try_from(Config::default())?
+    };
+    let overrides = read_config_value(merge_path)?;
+
+    let mut merged = base.clone();
+    merge_config_values(&mut merged, overrides)?;
+    // Refuse to write a config that trr itself could not load
+    check_merged_config(&merged)?;
+
+    let mut changes = Vec::new();
+    diff_toml(&base, &merged, "", &mut changes);
+    if changes.is_empty() {
+        println!("{} has no changes for your config.", merge_path.display());
+        return Ok(());
+    }
+
+    println!("Changes to {}:", config_path.display());
+    for change in &changes {
+        println!("  {change}");
+    }
+    if !crate::create::confirm("Write the merged config? Comments in the file are not preserved.")?
+    {
+        println!("Merge cancelled.");
+        return Ok(());
+    }
 
     if let Some(parent) = config_path.parent() {
         fs::create_dir_all(parent)?;
     }
+    fs::write(&config_path, toml::to_string_pretty(&merged)?)?;
+    println!(
+        "Merged {} into {}",
+        merge_path.display(),
+        config_path.display()
+    );
+    Ok(())
+}
+
+fn describe_path(path: &Path) -> String {
+    let path = env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+    let state = if path.exists() {
+        "exists"
+    } else {
+        "does not exist"
+    };
+    format!("{} ({state})", path.display())
+}
+
+pub fn print_config_path() -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = get_config_path()?;
+    println!("{}", describe_path(&config_path));
+
+    if let Some(profile) = env::var("TRR_PROFILE").ok().filter(|p| !p.is_empty()) {
+        let profile_path = get_profile_path(&config_path, &profile);
+        println!("profile {profile}: {}", describe_path(&profile_path));
+    }
+    Ok(())
+}
+
+pub fn init_config() -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = get_config_path()?;
 
     if !config_path.exists() {
-        let default_config = Config::default();
-        let toml_string = toml::to_string_pretty(&default_config)?;
-        fs::write(&config_path, toml_string)?;
+        write_default_config(&config_path)?;
         println!("Created new config file at: {}", config_path.display());
     }
 
@@ -162,11 +1116,337 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_config_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap();
+
+        assert_eq!(schema["title"], "Config");
+        assert!(schema["properties"]["settings"].is_object());
+        assert!(schema["properties"]["branch_aliases"].is_object());
+        assert!(schema["$defs"]["Settings"]["properties"]["repo_sync_path"].is_object());
+    }
+
+    #[test]
+    fn test_dirty_tree_policy_from_toml() {
+        let toml_str = r#"
+[settings]
+repo_sync_path = ".trr"
+tmux_window_init_commands = ""
+rsync_excludes = []
+dirty_tree_policy = "commit"
+
+[branch_aliases]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.settings.dirty_tree_policy, DirtyTreePolicy::Commit);
+
+        let toml_str = toml_str.replace("dirty_tree_policy = \"commit\"\n", "");
+        let config: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(config.settings.dirty_tree_policy, DirtyTreePolicy::Copy);
+    }
+
+    #[test]
+    fn test_current_branch_policy_from_toml() {
+        let toml_str = r#"
+[settings]
+repo_sync_path = ".trr"
+tmux_window_init_commands = ""
+rsync_excludes = []
+current_branch_policy = "stay"
+
+[branch_aliases]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.settings.current_branch_policy,
+            CurrentBranchPolicy::Stay
+        );
+
+        let toml_str = toml_str.replace("current_branch_policy = \"stay\"\n", "");
+        let config: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(
+            config.settings.current_branch_policy,
+            CurrentBranchPolicy::Error
+        );
+    }
+
+    #[test]
+    fn test_merge_config_keeps_host_sections() {
+        let mut base = validate(
+            r#"
+[settings]
+repo_sync_path = ".trr"
+tmux_window_init_commands = ""
+rsync_excludes = []
+
+[branch_aliases]
+
+[hosts.laptop]
+repo_sync_path = "~/copies"
+
+[hosts."desktop.local"]
+tmux_mode = "session"
+"#,
+        )
+        .unwrap();
+        let overrides = validate(
+            r#"
+[settings]
+use_gitignore = true
+"#,
+        )
+        .unwrap();
+
+        merge_config_values(&mut base, overrides).unwrap();
+        check_merged_config(&base).unwrap();
+        assert_eq!(
+            base["hosts"]["laptop"]["repo_sync_path"].as_str(),
+            Some("~/copies")
+        );
+        assert_eq!(
+            base["hosts"]["desktop.local"]["tmux_mode"].as_str(),
+            Some("session")
+        );
+        // What merge writes back still loads
+        validate(&toml::to_string(&base).unwrap()).unwrap();
+        assert_eq!(base["settings"]["use_gitignore"].as_bool(), Some(true));
+
+        // The merge file goes through the same key check as the config
+        let error = validate("[settings]\nuse_gitignor = true\n").unwrap_err();
+        assert!(error.contains("did you mean `use_gitignore`?"));
+    }
+
+    #[test]
+    fn test_merge_config_values_keeps_missing_keys() {
+        let mut base: toml::Value = toml::from_str(
+            r#"
+[settings]
+repo_sync_path = ".trr"
+tmux_window_init_commands = ""
+rsync_excludes = ["target"]
+
+[branch_aliases]
+"@f" = "feature/"
+"#,
+        )
+        .unwrap();
+        let overrides: toml::Value = toml::from_str(
+            r#"
+[settings]
+rsync_excludes = ["target", "node_modules"]
+use_gitignore = true
+
+[branch_aliases]
+"@b" = "bugfix/"
+
+[hosts.laptop.settings]
+repo_sync_path = "/tmp/trr"
+"#,
+        )
+        .unwrap();
+
+        let original = base.clone();
+        merge_config_values(&mut base, overrides).unwrap();
+        assert_eq!(base["settings"]["repo_sync_path"].as_str(), Some(".trr"));
+        assert_eq!(base["branch_aliases"]["@f"].as_str(), Some("feature/"));
+        assert_eq!(base["branch_aliases"]["@b"].as_str(), Some("bugfix/"));
+        assert!(base.get("hosts").is_none());
+        check_merged_config(&base).unwrap();
+
+        let mut changes = Vec::new();
+        diff_toml(&original, &base, "", &mut changes);
+        assert_eq!(
+            changes,
+            vec![
+                r#"+ branch_aliases.@b = "bugfix/""#,
+                r#"~ settings.rsync_excludes = ["target", "node_modules"] (was ["target"])"#,
+                "+ settings.use_gitignore = true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copy_strategy_from_toml() {
+        let toml_str = r#"
+[settings]
+repo_sync_path = ".trr"
+tmux_window_init_commands = ""
+rsync_excludes = []
+copy_strategy = "worktree"
+
+[branch_aliases]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.settings.copy_strategy, CopyStrategy::Worktree);
+        assert_eq!(
+            Config::default().settings.copy_strategy,
+            CopyStrategy::Rsync
+        );
+    }
+
+    #[test]
+    fn test_merge_toml_profile_overrides_per_key() {
+        let mut base: toml::Value = toml::from_str(
+            r#"
+[settings]
+repo_sync_path = ".trr"
+rsync_excludes = ["target"]
+
+[branch_aliases]
+"@f" = "feature"
+"#,
+        )
+        .unwrap();
+        let profile: toml::Value = toml::from_str(
+            r#"
+[settings]
+repo_sync_path = "~/work-copies"
+
+[branch_aliases]
+"@w" = "work"
+"#,
+        )
+        .unwrap();
+
+        merge_toml(&mut base, profile);
+
+        assert_eq!(
+            base["settings"]["repo_sync_path"].as_str(),
+            Some("~/work-copies")
+        );
+        assert_eq!(
+            base["settings"]["rsync_excludes"].as_array().unwrap().len(),
+            1
+        );
+        assert_eq!(base["branch_aliases"]["@f"].as_str(), Some("feature"));
+        assert_eq!(base["branch_aliases"]["@w"].as_str(), Some("work"));
+    }
+
+    #[test]
+    fn test_apply_host_overrides() {
+        let config_str = r#"
+[settings]
+repo_sync_path = ".trr"
+name_separator = "-"
+
+[hosts.laptop]
+repo_sync_path = "~/copies"
+
+[hosts."desktop.local"]
+repo_sync_path = "/data/copies"
+"#;
+
+        let mut value: toml::Value = toml::from_str(config_str).unwrap();
+        apply_host_overrides(&mut value, Some("laptop.example.com"));
+        assert_eq!(
+            value["settings"]["repo_sync_path"].as_str(),
+            Some("~/copies")
+        );
+        assert_eq!(value["settings"]["name_separator"].as_str(), Some("-"));
+        assert!(value.get("hosts").is_none());
+
+        let mut value: toml::Value = toml::from_str(config_str).unwrap();
+        apply_host_overrides(&mut value, Some("desktop.local"));
+        assert_eq!(
+            value["settings"]["repo_sync_path"].as_str(),
+            Some("/data/copies")
+        );
+
+        let mut value: toml::Value = toml::from_str(config_str).unwrap();
+        apply_host_overrides(&mut value, Some("server"));
+        assert_eq!(value["settings"]["repo_sync_path"].as_str(), Some(".trr"));
+    }
+
+    #[test]
+    fn test_append_rsync_excludes() {
+        let mut value: toml::Value =
+            toml::from_str("[settings]\nrsync_excludes = [\"target\"]\n").unwrap();
+
+        append_rsync_excludes(
+            &mut value,
+            &["node_modules".to_string(), "target".to_string()],
+        )
+        .unwrap();
+
+        let excludes: Vec<_> = value["settings"]["rsync_excludes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        assert_eq!(excludes, vec!["target", "node_modules"]);
+
+        let mut value: toml::Value = toml::from_str("").unwrap();
+        append_rsync_excludes(&mut value, &["dist".to_string()]).unwrap();
+        assert_eq!(
+            value["settings"]["rsync_excludes"][0].as_str(),
+            Some("dist")
+        );
+    }
+
+    #[test]
+    fn test_write_default_config() {
+        let test_dir = std::env::temp_dir().join(format!("trr_test_{}", ulid::Ulid::new()));
+        let config_path = test_dir.join("nested").join("config.toml");
+
+        write_default_config(&config_path).unwrap();
+        let config = load_with_profile(&config_path, None).unwrap();
+        assert_eq!(config.settings.repo_sync_path, ".trr");
+
+        // Clean up
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_load_with_profile() {
+        let test_dir = std::env::temp_dir().join(format!("trr_test_{}", ulid::Ulid::new()));
+        fs::create_dir_all(&test_dir).unwrap();
+        let config_path = test_dir.join("config.toml");
+        fs::write(
+            test_dir.join("work.toml"),
+            "[settings]\nname_separator = \"_\"\n",
+        )
+        .unwrap();
+
+        // Without a base config the profile is merged over the defaults
+        let config = load_with_profile(&config_path, Some("work")).unwrap();
+        assert_eq!(config.settings.name_separator, "_");
+        assert_eq!(config.settings.repo_sync_path, ".trr");
+
+        let config = load_with_profile(&config_path, None).unwrap();
+        assert_eq!(config.settings.name_separator, "-");
+
+        assert!(load_with_profile(&config_path, Some("missing")).is_err());
+
+        // Clean up
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_name_separator() {
+        assert!(validate("[settings]\nname_separator = \"_\"\n").is_ok());
+        assert!(validate("[settings]\nname_separator = \"--\"\n").is_ok());
+
+        for separator in [":", ".", "/", "-/-"] {
+            let err =
+                validate(&format!("[settings]\nname_separator = \"{separator}\"\n")).unwrap_err();
+            assert_eq!(
+                err,
+                "name_separator in [settings] must not contain ':', '.' or '/'"
+            );
+        }
+
+        // Host sections override settings, so they are checked too
+        let err = validate("[hosts.laptop]\nname_separator = \":\"\n").unwrap_err();
+        assert!(err.starts_with("name_separator in [hosts.laptop]"));
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
 
         assert_eq!(config.settings.repo_sync_path, ".trr");
+        assert_eq!(config.settings.name_separator, "-");
         assert!(!config.settings.tmux_window_init_commands.is_empty());
         assert!(
             config
@@ -175,17 +1455,99 @@ mod tests {
                 .contains(&"target".to_string())
         );
 
-        assert_eq!(
-            config.branch_aliases.get("@f"),
-            Some(&"feature".to_string())
-        );
-        assert_eq!(config.branch_aliases.get("@b"), Some(&"bugfix".to_string()));
+        assert_eq!(config.branch_aliases.get("@f"), Some(&"feature".into()));
+        assert_eq!(config.branch_aliases.get("@b"), Some(&"bugfix".into()));
         assert!(
             config
                 .branch_aliases
                 .get("@t")
                 .unwrap()
+                .expansion()
                 .starts_with("!echo")
         );
     }
+
+    #[test]
+    fn test_branch_alias_forms() {
+        let config_str = r#"
+[settings]
+repo_sync_path = ".trr"
+tmux_window_init_commands = "nvim"
+rsync_excludes = []
+
+[branch_aliases]
+"@f" = "feature"
+"@b" = { expansion = "bugfix", init_commands = "tmux split-window -h" }
+"#;
+
+        let config: Config = toml::from_str(config_str).unwrap();
+        let feature = &config.branch_aliases["@f"];
+        assert_eq!(feature.expansion(), "feature");
+        assert_eq!(feature.init_commands(), None);
+        let bugfix = &config.branch_aliases["@b"];
+        assert_eq!(bugfix.expansion(), "bugfix");
+        assert_eq!(bugfix.init_commands(), Some("tmux split-window -h"));
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_key() {
+        let config_str = r#"
+[settings]
+repo_sync_paths = ".trr"
+"#;
+
+        let error = validate(config_str).unwrap_err();
+        assert!(error.contains("unknown key `repo_sync_paths` in [settings]"));
+        assert!(error.contains("at line 3, column 1"));
+        assert!(error.contains("did you mean `repo_sync_path`?"));
+
+        let error = validate("[setings]\n").unwrap_err();
+        assert!(error.contains("at line 1, column 2"));
+        assert!(error.contains("did you mean `settings`?"));
+
+        let error = validate("[hosts.laptop]\nfoo = 1\n").unwrap_err();
+        assert!(error.contains("unknown key `foo` in [hosts.laptop]"));
+        assert!(!error.contains("did you mean"));
+
+        assert!(validate(&toml::to_string(&Config::default()).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_config_template() {
+        let template = config_template().unwrap();
+
+        // Every setting is listed, and the template loads as the default config
+        let (_, settings) = known_keys();
+        for key in &settings {
+            assert!(
+                template.contains(&format!("\n{key} = "))
+                    || template.contains(&format!("# {key} = ")),
+                "{key} is missing from the template"
+            );
+        }
+        assert!(template.contains("# copy_strategy = \"rsync\""));
+        assert!(template.contains("\nrepo_sync_path = \".trr\""));
+
+        assert!(validate(&template).is_ok());
+        let config: Config = toml::from_str(&template).unwrap();
+        let default = Config::default();
+        assert_eq!(
+            config.settings.repo_sync_path,
+            default.settings.repo_sync_path
+        );
+        assert_eq!(config.branch_aliases, default.branch_aliases);
+    }
+
+    #[test]
+    fn test_resolve_config_path_without_home() {
+        assert_eq!(resolve_config_path(None, None), Err(TrrError::NoHomeDir));
+        assert_eq!(
+            resolve_config_path(Some("/etc/trr.toml".to_string()), None),
+            Ok(PathBuf::from("/etc/trr.toml"))
+        );
+        assert_eq!(
+            resolve_config_path(None, Some(PathBuf::from("/home/user"))),
+            Ok(PathBuf::from("/home/user/.config/trr/config.toml"))
+        );
+    }
 }
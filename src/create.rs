@@ -1,18 +1,89 @@
-use crate::config::Config;
+use crate::config::{
+    BranchAlias, Config, CopyStrategy, CurrentBranchPolicy, DirtyTreePolicy, IdScheme, TmuxMode,
+};
+use crate::delete::{find_tmux_session_or_window, kill_tmux_session_or_window};
+use crate::git::{current_branch, get_repo_prefix, get_source_repo_name};
+use crate::hooks::{self, HookContext};
+use crate::template;
+use crate::watch;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use skim::prelude::*;
+use std::collections::HashMap;
 use std::fs;
-use std::io::IsTerminal;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
 use ulid::Ulid;
 
-#[derive(Serialize, Deserialize)]
+// With --print-session, stdout is reserved for the tmux name so scripts can capture it
+macro_rules! status {
+    ($to_stderr:expr, $($arg:tt)*) => {
+        if $to_stderr {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct RepositoryMetadata {
+    // Older files only have the ULID in their file name. Holds the ID of any scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ulid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_scheme: Option<IdScheme>,
     pub branch: String,
     pub created_at: chrono::DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub directory: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linked_paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub branch_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_duration_ms: Option<u64>,
+    // Recorded so a recreated session can substitute the same @@args
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub worktree: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_group: Option<String>,
+    // The init command flags given to create, so switch renders the same commands
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub template_engine: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quiet_tmux: bool,
+    // Older files don't record it, so delete falls back to probing tmux
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_kind: Option<TmuxKind>,
+}
+
+// What create opened for a copy, so delete looks for the same thing later
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TmuxKind {
+    Window,
+    Session,
+    RepoSession,
+    None,
 }
 
 pub fn read_ulid_metadata(path: &Path) -> Result<RepositoryMetadata, Box<dyn std::error::Error>> {
@@ -28,370 +99,2443 @@ pub fn read_ulid_metadata(path: &Path) -> Result<RepositoryMetadata, Box<dyn std
         branch: branch.clone(),
         created_at: Utc::now(),
         directory: Some(directory),
+        ..Default::default()
     })
 }
 
-fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-    let config_path = if let Ok(path) = std::env::var("TRR_CONFIG_PATH") {
-        expand_tilde(&path)
-    } else {
-        dirs::home_dir()
-            .expect("Failed to get home directory")
-            .join(".config")
-            .join("trr")
-            .join("config.toml")
-    };
+fn metadata_file_name(template: &str, ulid: &str, directory: &str) -> String {
+    let name = template
+        .replace("{ulid}", ulid)
+        .replace("{directory}", &branch_to_directory_name(directory));
+    format!("{name}.json")
+}
 
-    if !config_path.exists() {
-        return Ok(Config::default());
+pub fn metadata_file_path(
+    config: &Config,
+    ulid: &str,
+    directory: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let template = &config.settings.metadata_filename;
+    if !template.contains("{ulid}") && !template.contains("{directory}") {
+        return Err("metadata_filename must contain {ulid} or {directory}".into());
     }
 
-    let config_str = fs::read_to_string(&config_path)?;
-    let config: Config = toml::from_str(&config_str)?;
-    Ok(config)
+    Ok(PathBuf::from(&config.settings.repo_sync_path)
+        .join(".trr-sys")
+        .join(metadata_file_name(template, ulid, directory)))
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
-    if path.starts_with('~') {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(&path[2..]);
-        }
+// UUIDv7 shares the ULID layout (48-bit millisecond timestamp, then random bits),
+// so a ULID only needs its version and variant bits set
+fn ulid_to_uuid_v7(ulid: Ulid) -> String {
+    let bits = (ulid.0 & !(0xF << 76) & !(0b11 << 62)) | (0x7 << 76) | (0b10 << 62);
+    let hex = format!("{bits:032x}");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+pub fn generate_id(scheme: IdScheme) -> String {
+    let ulid = Ulid::new();
+    match scheme {
+        IdScheme::Ulid => ulid.to_string(),
+        IdScheme::Uuid => ulid_to_uuid_v7(ulid),
+        // The suffix keeps copies created in the same millisecond apart
+        IdScheme::Timestamp => format!(
+            "{}-{:04x}",
+            chrono::DateTime::<Utc>::from(ulid.datetime()).format("%Y%m%dT%H%M%S%3fZ"),
+            ulid.random() & 0xFFFF
+        ),
+    }
+}
+
+pub fn write_metadata(
+    config: &Config,
+    ulid: &str,
+    metadata: &RepositoryMetadata,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let directory = metadata
+        .directory
+        .clone()
+        .unwrap_or_else(|| branch_to_directory_name(&metadata.branch));
+    let ulid_file_path = metadata_file_path(config, ulid, &directory)?;
+    if let Some(trr_sys_path) = ulid_file_path.parent() {
+        fs::create_dir_all(trr_sys_path)?;
     }
-    PathBuf::from(path)
+
+    let json_content = serde_json::to_string_pretty(metadata)?;
+    fs::write(&ulid_file_path, json_content)?;
+    Ok(ulid_file_path)
+}
+
+#[derive(Serialize)]
+pub struct AliasExpansion {
+    pub alias: Option<String>,
+    pub is_command: bool,
+    pub expansion: Option<String>,
+    pub branch: String,
 }
 
-fn expand_alias(branch: &str, config: &Config) -> String {
+pub fn resolve_alias(branch: &str, config: &Config) -> AliasExpansion {
     for (alias, expansion) in &config.branch_aliases {
+        let expansion = expansion.expansion();
         if branch.starts_with(alias) {
             let suffix = &branch[alias.len()..];
             if let Some(cmd) = expansion.strip_prefix('!') {
                 if let Ok(output) = Command::new("sh").arg("-c").arg(cmd).output() {
                     let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    return format!("{result}{suffix}");
+                    return AliasExpansion {
+                        alias: Some(alias.clone()),
+                        is_command: true,
+                        branch: format!("{result}{suffix}"),
+                        expansion: Some(result),
+                    };
                 }
             } else {
-                return format!("{expansion}{suffix}");
+                return AliasExpansion {
+                    alias: Some(alias.clone()),
+                    is_command: false,
+                    expansion: Some(expansion.to_string()),
+                    branch: format!("{expansion}{suffix}"),
+                };
             }
         }
     }
-    branch.to_string()
+
+    AliasExpansion {
+        alias: None,
+        is_command: false,
+        expansion: None,
+        branch: branch.to_string(),
+    }
+}
+
+// Characters a shell or a tmux target would interpret; they end up unescaped in
+// directory names, session names and init commands
+const UNSAFE_BRANCH_CHARS: &[char] = &[
+    '$', '`', ';', '&', '|', '<', '>', '(', ')', '{', '}', '*', '?', '[', ']', '!', '\'', '"',
+    '\\', '~', '^', ':', '#',
+];
+
+pub fn validate_branch_name(branch: &str) -> Result<(), String> {
+    if branch.is_empty() {
+        return Err("Branch name must not be empty".to_string());
+    }
+    if let Some(c) = branch
+        .chars()
+        .find(|c| c.is_whitespace() || c.is_control() || UNSAFE_BRANCH_CHARS.contains(c))
+    {
+        return Err(format!(
+            "Branch name '{branch}' contains {c:?}, which breaks directory names and tmux targets. Pass --force to use it anyway."
+        ));
+    }
+    Ok(())
 }
 
 pub fn branch_to_directory_name(branch: &str) -> String {
     branch.replace('/', "-")
 }
 
-fn check_tmux_available() -> bool {
-    Command::new("which")
-        .arg("tmux")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+// With --no-sanitize-dir a slashed branch becomes nested directories
+fn copy_directory_name(dir_prefix: &str, branch: &str, no_sanitize_dir: bool) -> String {
+    if no_sanitize_dir {
+        format!("{dir_prefix}{branch}")
+    } else {
+        format!("{dir_prefix}{}", branch_to_directory_name(branch))
+    }
 }
 
-fn get_repo_name() -> Option<String> {
-    let output = Command::new("git")
-        .arg("remote")
-        .arg("get-url")
-        .arg("origin")
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+// tmux reads '.' in a target as a pane delimiter, and '/' makes targets ambiguous
+pub fn sanitize_tmux_name(branch_name: &str) -> String {
+    branch_name.replace(['/', '.'], "-")
+}
 
-    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    let repo_name = if url.starts_with("https://") || url.starts_with("http://") {
-        url.split('/')
-            .next_back()?
-            .trim_end_matches(".git")
-            .to_string()
-    } else if url.contains(':') {
-        url.split(':')
-            .next_back()?
-            .split('/')
-            .next_back()?
-            .trim_end_matches(".git")
-            .to_string()
-    } else {
-        return None;
-    };
+// A prefix must not add path components or break the directory name in shells
+fn sanitize_dir_prefix(prefix: &str) -> String {
+    prefix
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_whitespace() || c.is_control() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
 
-    Some(repo_name)
+fn render_dir_prefix(template: &str, ulid: &str) -> String {
+    let date = chrono::Local::now().format("%Y%m%d").to_string();
+    sanitize_dir_prefix(&template.replace("{date}", &date).replace("{ulid}", ulid))
 }
 
-fn get_repo_prefix() -> String {
-    if let Some(repo_name) = get_repo_name() {
-        repo_name.chars().take(3).collect()
-    } else {
-        std::env::current_dir()
-            .ok()
-            .and_then(|dir| {
-                dir.file_name()
-                    .map(|name| name.to_string_lossy().to_string())
-            })
-            .map(|name| name.chars().take(3).collect())
-            .unwrap_or_else(|| "trr".to_string())
-    }
+pub fn tmux_target_name(repo_prefix: &str, separator: &str, branch_name: &str) -> String {
+    format!(
+        "{repo_prefix}{separator}{}",
+        sanitize_tmux_name(branch_name)
+    )
 }
 
-fn setup_tmux_environment(
-    branch_name: &str,
+fn link_shared_paths(
+    source_dir: &Path,
     target_dir: &Path,
-    init_commands: &str,
-    args: &[String],
-) -> Result<(), Box<dyn std::error::Error>> {
-    if !check_tmux_available() {
-        eprintln!("Warning: tmux is not installed. Skipping tmux setup.");
-        eprintln!("To use tmux integration, please install tmux.");
-        return Ok(());
+    link_paths: &[String],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut linked_paths = Vec::new();
+
+    for link_path in link_paths {
+        let link_path = link_path.trim_end_matches('/');
+        let source = source_dir.join(link_path);
+        if !source.exists() {
+            eprintln!("Warning: link path '{link_path}' does not exist in source. Skipping.");
+            continue;
+        }
+
+        // The path may be missing from the copy when it is excluded from rsync
+        let destination = target_dir.join(link_path);
+        if destination.is_symlink() || destination.is_file() {
+            fs::remove_file(&destination)?;
+        } else if destination.is_dir() {
+            fs::remove_dir_all(&destination)?;
+        }
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        std::os::unix::fs::symlink(&source, &destination)?;
+        linked_paths.push(link_path.to_string());
     }
 
-    let repo_prefix = get_repo_prefix();
+    Ok(linked_paths)
+}
 
-    let args_str = args.join(" ");
-    let processed_commands = init_commands.replace("@@args", &args_str);
+// Present in a copy while rsync runs and holds the ULID of that attempt,
+// so an interrupted copy can be told apart from a finished one
+const IN_PROGRESS_MARKER: &str = ".trr-in-progress";
 
-    let in_tmux = std::env::var("TMUX").is_ok();
+fn read_in_progress_marker(target_dir: &Path) -> Option<String> {
+    fs::read_to_string(target_dir.join(IN_PROGRESS_MARKER))
+        .ok()
+        .map(|content| content.trim().to_string())
+}
 
-    if in_tmux {
-        let window_name = format!("{repo_prefix}-{branch_name}");
+// Undoes a half-finished copy when dropped before `disarm`, so every early
+// return between writing the metadata and checking out the branch leaves
+// nothing behind that would block creating the branch again
+struct CopyRollback {
+    metadata_path: PathBuf,
+    target_dir: PathBuf,
+    worktree: bool,
+    // `git worktree add -b` creates the branch in the source repository
+    worktree_branch: Option<String>,
+    keep_interrupted_copy: bool,
+    armed: bool,
+}
 
-        println!("Creating new tmux window '{window_name}' in current session...");
-        let create_window = Command::new("tmux")
-            .arg("new-window")
-            .arg("-n")
-            .arg(&window_name)
-            .arg("-c")
-            .arg(target_dir.to_string_lossy().to_string())
-            .output()?;
+impl CopyRollback {
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
 
-        if !create_window.status.success() {
-            eprintln!(
-                "Failed to create tmux window. stderr: {}",
-                String::from_utf8_lossy(&create_window.stderr)
-            );
-            return Err("Failed to create tmux window".into());
+impl Drop for CopyRollback {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
         }
+        let _ = fs::remove_file(&self.metadata_path);
 
-        if !processed_commands.trim().is_empty() {
-            for command in processed_commands.trim().lines() {
-                if !command.trim().is_empty() {
-                    Command::new("tmux")
-                        .arg("send-keys")
-                        .arg("-t")
-                        .arg(&window_name)
-                        .arg(command)
-                        .arg("Enter")
-                        .status()?;
-                }
+        // An interrupted rsync is kept for --resume, recognizable by its marker
+        if self.keep_interrupted_copy && read_in_progress_marker(&self.target_dir).is_some() {
+            return;
+        }
+        if self.worktree {
+            let _ = Command::new("git")
+                .arg("worktree")
+                .arg("remove")
+                .arg("--force")
+                .arg(&self.target_dir)
+                .output();
+            if let Some(branch) = &self.worktree_branch {
+                let _ = Command::new("git")
+                    .arg("branch")
+                    .arg("-D")
+                    .arg(branch)
+                    .output();
             }
         }
+        let _ = fs::remove_dir_all(&self.target_dir);
+    }
+}
 
-        Command::new("tmux")
-            .arg("select-window")
-            .arg("-t")
-            .arg(&window_name)
-            .status()?;
+fn get_head_commit(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("HEAD")
+        .current_dir(dir)
+        .output()
+        .ok()?;
 
-        println!("✓ Switched to new window '{window_name}'");
-    } else if std::io::stdin().is_terminal() {
-        let session_name = format!("{repo_prefix}-{branch_name}");
+    if !output.status.success() {
+        return None;
+    }
 
-        println!(
-            "Creating tmux session '{}' in directory '{}'",
-            session_name,
-            target_dir.display()
-        );
-        let create_result = Command::new("tmux")
-            .arg("new-session")
-            .arg("-d")
-            .arg("-s")
-            .arg(&session_name)
-            .arg("-c")
-            .arg(target_dir.to_string_lossy().to_string())
-            .output()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
-        if !create_result.status.success() {
-            eprintln!(
-                "Failed to create tmux session. stderr: {}",
-                String::from_utf8_lossy(&create_result.stderr)
-            );
-            return Err("Failed to create tmux session".into());
-        }
+pub fn confirm(prompt: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    // Prompts go to stderr so they don't end up in --print-session output
+    eprint!("{prompt} [y/N]: ");
+    io::stderr().flush()?;
 
-        if !processed_commands.trim().is_empty() {
-            for command in processed_commands.trim().lines() {
-                if !command.trim().is_empty() {
-                    Command::new("tmux")
-                        .arg("send-keys")
-                        .arg("-t")
-                        .arg(&session_name)
-                        .arg(command)
-                        .arg("Enter")
-                        .status()?;
-                }
-            }
-        }
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-        println!("Attaching to tmux session '{session_name}'...");
-        Command::new("tmux")
-            .arg("attach-session")
-            .arg("-t")
-            .arg(&session_name)
-            .status()?;
-    } else {
-        println!(
-            "Not in a terminal environment. Navigate to {} to start working.",
-            target_dir.display()
-        );
-    }
+    Ok(input.trim().to_lowercase() == "y")
+}
 
-    Ok(())
+fn parse_stats_number(value: &str) -> u64 {
+    value
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .replace([',', '.'], "")
+        .parse()
+        .unwrap_or(0)
 }
 
-pub fn create_repo(
-    branch: &str,
-    args: &[String],
-    debug: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let config = load_config()?;
-    let expanded_branch = expand_alias(branch, &config);
-    let directory_name = branch_to_directory_name(&expanded_branch);
+// Returns the number of regular files and total bytes from rsync --stats output
+fn parse_rsync_stats(output: &str) -> (u64, u64) {
+    let mut files = 0;
+    let mut size = 0;
 
-    if debug {
-        eprintln!("Debug: Branch alias expansion: {branch} -> {expanded_branch}");
-        eprintln!("Debug: Directory name: {directory_name}");
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix("Number of regular files transferred:") {
+            files = parse_stats_number(value);
+        } else if let Some(value) = line.strip_prefix("Total transferred file size:") {
+            size = parse_stats_number(value);
+        }
     }
 
-    let target_dir = PathBuf::from(&config.settings.repo_sync_path).join(&directory_name);
-    if target_dir.exists() {
-        return Err(format!(
-            "Directory '{}' already exists. Use a different branch name or delete the existing one first.",
-            target_dir.display()
-        ).into());
-    }
+    (files, size)
+}
 
-    let ulid = Ulid::new();
+const WELL_KNOWN_LARGE_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    ".venv",
+    "venv",
+    "dist",
+    "build",
+    ".next",
+    "vendor",
+];
+
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
 
-    let current_dir = std::env::current_dir()?;
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
 
-    let trr_sys_path = PathBuf::from(&config.settings.repo_sync_path).join(".trr-sys");
-    fs::create_dir_all(&trr_sys_path)?;
+fn is_excluded(relative_path: &str, name: &str, excludes: &[String]) -> bool {
+    excludes.iter().any(|exclude| {
+        let exclude = exclude.trim_start_matches('/').trim_end_matches('/');
+        exclude == name || exclude == relative_path
+    })
+}
 
-    let metadata = RepositoryMetadata {
-        branch: expanded_branch.clone(),
-        created_at: Utc::now(),
-        directory: Some(directory_name.clone()),
+// Checks top-level directories and well-known build/dependency directories one
+// level below them, without walking the whole tree for candidates
+fn find_large_dirs(
+    source_dir: &Path,
+    threshold_bytes: u64,
+    excludes: &[String],
+) -> Vec<(String, u64)> {
+    let mut candidates = Vec::new();
+    let Ok(entries) = fs::read_dir(source_dir) else {
+        return Vec::new();
     };
-    let ulid_file_path = trr_sys_path.join(format!("{ulid}.json"));
-    let json_content = serde_json::to_string_pretty(&metadata)?;
-    fs::write(&ulid_file_path, json_content)?;
 
-    fs::create_dir_all(&target_dir)?;
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" || is_excluded(&name, &name, excludes) {
+            continue;
+        }
+        candidates.push((name.clone(), entry.path()));
+
+        if let Ok(children) = fs::read_dir(entry.path()) {
+            for child in children.flatten() {
+                let child_name = child.file_name().to_string_lossy().to_string();
+                let relative_path = format!("{name}/{child_name}");
+                if WELL_KNOWN_LARGE_DIRS.contains(&child_name.as_str())
+                    && child.file_type().is_ok_and(|t| t.is_dir())
+                    && !is_excluded(&relative_path, &child_name, excludes)
+                {
+                    candidates.push((relative_path, child.path()));
+                }
+            }
+        }
+    }
+
+    let mut large_dirs: Vec<(String, u64)> = candidates
+        .into_iter()
+        .map(|(name, path)| (name, dir_size(&path)))
+        .filter(|(_, size)| *size >= threshold_bytes)
+        .collect();
+    large_dirs.sort();
+    large_dirs
+}
 
-    let mut rsync_command = Command::new("rsync");
-    rsync_command.arg("-a");
+fn get_git_dir(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--git-dir")
+        .current_dir(dir)
+        .output()
+        .ok()?;
 
-    if debug {
-        rsync_command.arg("-v");
+    if !output.status.success() {
+        return None;
     }
 
-    // Always exclude repo_sync_path
-    rsync_command
-        .arg("--exclude")
-        .arg(&config.settings.repo_sync_path);
+    Some(dir.join(String::from_utf8_lossy(&output.stdout).trim()))
+}
 
-    // Add user-defined excludes
-    for exclude in &config.settings.rsync_excludes {
-        rsync_command.arg("--exclude").arg(exclude);
+fn detect_git_operation(git_dir: &Path) -> Option<&'static str> {
+    const OPERATIONS: &[(&str, &str)] = &[
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("MERGE_HEAD", "merge"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+        ("BISECT_LOG", "bisect"),
+    ];
+
+    OPERATIONS
+        .iter()
+        .find(|(marker, _)| git_dir.join(marker).exists())
+        .map(|(_, operation)| *operation)
+}
+
+pub fn git_status_porcelain(dir: &Path, dirty_ignore: &[String]) -> Option<String> {
+    let mut status_command = Command::new("git");
+    status_command
+        .arg("status")
+        .arg("--porcelain")
+        .current_dir(dir);
+
+    if !dirty_ignore.is_empty() {
+        status_command.arg("--").arg(".");
+        for pathspec in dirty_ignore {
+            status_command.arg(format!(":(exclude){pathspec}"));
+        }
+    }
+
+    let output = status_command.output().ok()?;
+    if !output.status.success() {
+        return None;
     }
 
-    let rsync_result = rsync_command
-        .arg(format!("{}/", current_dir.display()))
-        .arg(format!("{}/", target_dir.display()))
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn is_working_tree_dirty(dir: &Path, dirty_ignore: &[String]) -> bool {
+    git_status_porcelain(dir, dirty_ignore).is_some_and(|status| !status.is_empty())
+}
+
+// rsync copies submodule checkouts as plain trees whose gitlinks may not match the
+// new branch, and a worktree starts with empty submodule directories
+fn update_submodules(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    // Progress goes to stderr so --print-session output stays clean
+    let status = Command::new("git")
+        .arg("submodule")
+        .arg("update")
+        .arg("--init")
+        .arg("--recursive")
+        .arg("--progress")
+        .current_dir(dir)
+        .stdout(io::stderr())
         .status()?;
 
-    if !rsync_result.success() {
-        return Err("rsync failed".into());
+    if !status.success() {
+        return Err(format!("Failed to update submodules ({status})").into());
     }
+    Ok(())
+}
 
-    let absolute_target_dir = current_dir.join(&target_dir);
-    let checkout_result = Command::new("git")
-        .arg("checkout")
-        .arg("-b")
-        .arg(&expanded_branch)
-        .current_dir(&absolute_target_dir)
+fn commit_copied_changes(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let add_result = Command::new("git")
+        .arg("add")
+        .arg("-A")
+        .current_dir(dir)
         .output()?;
 
-    if !checkout_result.status.success() {
+    if !add_result.status.success() {
         eprintln!(
-            "Failed to create git branch. stderr: {}",
-            String::from_utf8_lossy(&checkout_result.stderr)
+            "Failed to stage copied changes. stderr: {}",
+            String::from_utf8_lossy(&add_result.stderr)
         );
-        return Err("Failed to create git branch".into());
+        return Err("Failed to stage copied changes".into());
     }
 
-    println!("Repository duplicated successfully:");
-    println!("  Branch: {branch} -> {expanded_branch}");
-    println!("  ULID: {ulid}");
-    println!("  Target: {}", target_dir.display());
+    let commit_result = Command::new("git")
+        .arg("commit")
+        .arg("-m")
+        .arg("WIP: uncommitted changes copied by trr")
+        .current_dir(dir)
+        .output()?;
 
-    setup_tmux_environment(
-        &expanded_branch,
-        &absolute_target_dir,
-        &config.settings.tmux_window_init_commands,
-        args,
-    )?;
+    if !commit_result.status.success() {
+        eprintln!(
+            "Failed to commit copied changes. stderr: {}",
+            String::from_utf8_lossy(&commit_result.stderr)
+        );
+        return Err("Failed to commit copied changes".into());
+    }
 
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
+fn check_tmux_available() -> bool {
+    Command::new("which")
+        .arg("tmux")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
 
-    #[test]
-    fn test_branch_to_directory_name() {
-        assert_eq!(branch_to_directory_name("feature/test"), "feature-test");
-        assert_eq!(branch_to_directory_name("fix/bug/123"), "fix-bug-123");
-        assert_eq!(branch_to_directory_name("simple-branch"), "simple-branch");
-        assert_eq!(branch_to_directory_name(""), "");
+fn check_gh_available() -> bool {
+    Command::new("which")
+        .arg("gh")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn get_pr_head_branch(pr_number: u64) -> Result<String, Box<dyn std::error::Error>> {
+    if !check_gh_available() {
+        return Err("gh is not installed. Install the GitHub CLI to use --from-pr.".into());
     }
 
-    #[test]
-    fn test_expand_alias_static() {
-        let mut config = Config::default();
-        config.branch_aliases.clear();
-        config
-            .branch_aliases
-            .insert("@f".to_string(), "feature".to_string());
-        config
-            .branch_aliases
-            .insert("@b".to_string(), "bugfix".to_string());
+    let output = Command::new("gh")
+        .arg("pr")
+        .arg("view")
+        .arg(pr_number.to_string())
+        .arg("--json")
+        .arg("headRefName")
+        .arg("--jq")
+        .arg(".headRefName")
+        .output()?;
 
-        assert_eq!(expand_alias("@f/test", &config), "feature/test");
-        assert_eq!(expand_alias("@b/123", &config), "bugfix/123");
-        assert_eq!(expand_alias("@f", &config), "feature");
-        assert_eq!(expand_alias("no-alias", &config), "no-alias");
+    if !output.status.success() {
+        eprintln!(
+            "Failed to look up pull request #{pr_number}. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err(format!("Failed to look up pull request #{pr_number}").into());
     }
 
-    #[test]
-    fn test_read_ulid_metadata_json() {
-        let temp_dir = std::env::temp_dir();
-        let test_id = Ulid::new().to_string();
-        let file_path = temp_dir.join(format!("trr_test_{test_id}.json"));
-
-        let metadata = RepositoryMetadata {
-            branch: "feature/test".to_string(),
-            created_at: Utc::now(),
-            directory: Some("feature-test".to_string()),
-        };
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if branch.is_empty() {
+        return Err(format!("Pull request #{pr_number} has no head branch").into());
+    }
+    Ok(branch)
+}
 
-        let json = serde_json::to_string_pretty(&metadata).unwrap();
+// Shells with HISTCONTROL=ignorespace keep space-prefixed commands out of history
+fn prefix_with_space(commands: &str) -> String {
+    commands
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.to_string()
+            } else {
+                format!(" {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn tmux_command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// tmux answers at once unless its server is wedged, so with
+// settings.tmux_command_timeout_secs a stuck call is killed instead of hanging create
+fn run_tmux(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> Result<Output, Box<dyn std::error::Error>> {
+    let Some(timeout) = timeout else {
+        return Ok(command.output()?);
+    };
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let deadline = Instant::now() + timeout;
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!(
+                "`{}` timed out after {timeout:?}",
+                tmux_command_line(command)
+            )
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Ok(child.wait_with_output()?)
+}
+
+// Like run_tmux, but a non-zero exit is an error naming the command and its stderr
+fn run_tmux_checked(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output = run_tmux(command, timeout)?;
+    if !output.status.success() {
+        return Err(format!(
+            "`{}` failed: {}",
+            tmux_command_line(command),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// With continue_on_tmux_error a failed step is collected instead of aborting
+fn tolerate_tmux_error(
+    result: Result<(), Box<dyn std::error::Error>>,
+    best_effort: bool,
+    errors: &mut Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match result {
+        Err(e) if best_effort => {
+            errors.push(e.to_string());
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+const ONCE_ANNOTATION: &str = "# once";
+
+// Lines ending in `# once` run when the copy is created but not when its session is
+// recreated later, e.g. a `git reset --hard` that would discard work
+fn filter_once_commands(commands: &str, first_creation: bool) -> String {
+    commands
+        .lines()
+        .filter_map(|line| match line.trim_end().strip_suffix(ONCE_ANNOTATION) {
+            Some(command) if first_creation => Some(command.trim_end()),
+            Some(_) => None,
+            None => Some(line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn send_init_commands(
+    target: &str,
+    commands: &str,
+    best_effort: bool,
+    timeout: Option<Duration>,
+    errors: &mut Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for command in commands.trim().lines() {
+        if command.trim().is_empty() {
+            continue;
+        }
+
+        let result = run_tmux_checked(
+            Command::new("tmux")
+                .arg("send-keys")
+                .arg("-t")
+                .arg(target)
+                .arg(command)
+                .arg("Enter"),
+            timeout,
+        );
+        tolerate_tmux_error(result, best_effort, errors)?;
+    }
+
+    Ok(())
+}
+
+struct TmuxSetup<'a> {
+    mode: TmuxMode,
+    repo_prefix: &'a str,
+    branch_name: &'a str,
+    target_dir: &'a Path,
+    init_commands: &'a str,
+    args: &'a [String],
+    separator: &'a str,
+    best_effort: bool,
+    print_session: bool,
+    detach: bool,
+    on_attach_command: Option<&'a str>,
+    window_index: Option<u32>,
+    renumber_windows: Option<bool>,
+    label: Option<&'a str>,
+    session_group: Option<&'a str>,
+    command_timeout: Option<Duration>,
+    first_creation: bool,
+}
+
+// `new-session -t` accepts a session group name since tmux 2.4
+const MIN_SESSION_GROUP_VERSION: (u32, u32) = (2, 4);
+
+fn parse_tmux_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.trim().strip_prefix("tmux ")?;
+    let version = version.strip_prefix("next-").unwrap_or(version);
+    let (major, rest) = version.split_once('.')?;
+    let minor: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+fn session_groups_supported() -> bool {
+    let Ok(output) = Command::new("tmux").arg("-V").output() else {
+        return false;
+    };
+    // Development builds like "tmux master" have no version number
+    parse_tmux_version(&String::from_utf8_lossy(&output.stdout))
+        .is_none_or(|version| version >= MIN_SESSION_GROUP_VERSION)
+}
+
+fn session_group_exists(group: &str) -> bool {
+    let Ok(output) = Command::new("tmux")
+        .arg("list-sessions")
+        .arg("-F")
+        .arg("#{session_name}:#{session_group}")
+        .output()
+    else {
+        return false;
+    };
+
+    // tmux forbids ':' in session names, so it separates the two fields
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.split(':').any(|name| name == group))
+}
+
+// Grouped sessions share their windows, so the copy gets its own window that
+// shows up in every session of the group. The first copy starts the group with
+// a session named after it
+fn create_grouped_session(
+    session_name: &str,
+    group: &str,
+    target_dir: &Path,
+    timeout: Option<Duration>,
+) -> Result<Output, Box<dyn std::error::Error>> {
+    if !session_group_exists(group) {
+        let group_result = run_tmux(
+            Command::new("tmux")
+                .arg("new-session")
+                .arg("-d")
+                .arg("-s")
+                .arg(group)
+                .arg("-c")
+                .arg(target_dir),
+            timeout,
+        )?;
+        if !group_result.status.success() {
+            return Ok(group_result);
+        }
+    }
+
+    let session_result = run_tmux(
+        Command::new("tmux")
+            .arg("new-session")
+            .arg("-d")
+            .arg("-s")
+            .arg(session_name)
+            .arg("-t")
+            .arg(group),
+        timeout,
+    )?;
+    if !session_result.status.success() {
+        return Ok(session_result);
+    }
+
+    run_tmux(
+        Command::new("tmux")
+            .arg("new-window")
+            .arg("-t")
+            .arg(format!("{session_name}:"))
+            .arg("-n")
+            .arg(session_name)
+            .arg("-c")
+            .arg(target_dir),
+        timeout,
+    )
+}
+
+// Shared by init commands and on_attach_command
+pub fn expand_branch_placeholders(command: &str, branch_name: &str, target_dir: &Path) -> String {
+    command
+        .replace("@@branch", branch_name)
+        .replace("@@dir", &target_dir.to_string_lossy())
+}
+
+const LABEL_OPTION: &str = "@trr_label";
+
+// Shown through the user's status line config, e.g. #{@trr_label}.
+// Best-effort like on_attach_command
+fn set_window_label(target: &str, label: Option<&str>, timeout: Option<Duration>) {
+    let Some(label) = label else {
+        return;
+    };
+
+    let result = run_tmux_checked(
+        Command::new("tmux")
+            .arg("set-option")
+            .arg("-w")
+            .arg("-t")
+            .arg(target)
+            .arg(LABEL_OPTION)
+            .arg(label),
+        timeout,
+    );
+
+    if let Err(e) = result {
+        eprintln!("Warning: failed to set {LABEL_OPTION} on '{target}': {e}");
+    }
+}
+
+// Best-effort: a failure here must not affect attaching
+pub fn send_on_attach_command(target: &str, command: &str, timeout: Option<Duration>) {
+    if command.trim().is_empty() {
+        return;
+    }
+
+    let result = run_tmux_checked(
+        Command::new("tmux")
+            .arg("send-keys")
+            .arg("-t")
+            .arg(target)
+            .arg(command)
+            .arg("Enter"),
+        timeout,
+    );
+
+    if let Err(e) = result {
+        eprintln!("Warning: failed to send on_attach_command to '{target}': {e}");
+    }
+}
+
+// Switches to a window/session that is already open for the copy instead of
+// creating a duplicate
+fn reuse_tmux_target(
+    name: &str,
+    is_window: bool,
+    detach: bool,
+    print_session: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    status!(
+        print_session,
+        "Reusing the existing tmux {} '{name}' (pass --no-reuse to reopen it)",
+        if is_window { "window" } else { "session" }
+    );
+    if detach {
+        return Ok(());
+    }
+    crate::switch::attach(name, is_window)
+}
+
+// TMUX_PANE is the pane trr runs in, which need not be the session's active one
+fn is_current_tmux_target(name: &str, is_window: bool) -> bool {
+    let Ok(pane) = std::env::var("TMUX_PANE") else {
+        return false;
+    };
+    let format = if is_window {
+        "#{session_name}:#{window_name}"
+    } else {
+        "#{session_name}"
+    };
+    Command::new("tmux")
+        .arg("display-message")
+        .arg("-p")
+        .arg("-t")
+        .arg(pane)
+        .arg(format)
+        .output()
+        .is_ok_and(|output| {
+            output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == name
+        })
+}
+
+// Killing the window or session trr runs in would take trr down with it
+fn close_tmux_target(name: &str, is_window: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let kind = if is_window { "window" } else { "session" };
+    if is_current_tmux_target(name, is_window) {
+        return Err(format!(
+            "trr is running in the tmux {kind} '{name}' and cannot close it; run it from another one"
+        )
+        .into());
+    }
+    kill_tmux_session_or_window(name, is_window)
+}
+
+// Decided up front so it can be stored in the metadata before tmux runs
+fn planned_tmux_kind(mode: TmuxMode, in_tmux: bool, can_attach: bool) -> TmuxKind {
+    match resolve_tmux_mode(mode, in_tmux) {
+        _ if !can_attach => TmuxKind::None,
+        TmuxMode::RepoSession => TmuxKind::RepoSession,
+        TmuxMode::Window => TmuxKind::Window,
+        _ => TmuxKind::Session,
+    }
+}
+
+// A window needs a current session, so window mode falls back to a session outside tmux
+fn resolve_tmux_mode(mode: TmuxMode, in_tmux: bool) -> TmuxMode {
+    match mode {
+        TmuxMode::Auto | TmuxMode::Window if in_tmux => TmuxMode::Window,
+        TmuxMode::Auto | TmuxMode::Window => TmuxMode::Session,
+        mode => mode,
+    }
+}
+
+pub fn repo_session_target(repo_prefix: &str, branch_name: &str) -> String {
+    format!("{repo_prefix}:{}", sanitize_tmux_name(branch_name))
+}
+
+fn setup_repo_session(
+    setup: &TmuxSetup,
+    processed_commands: &str,
+    in_tmux: bool,
+    errors: &mut Vec<String>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let session_name = setup.repo_prefix;
+    let target = repo_session_target(session_name, setup.branch_name);
+    let target_dir = setup.target_dir;
+
+    let timeout = setup.command_timeout;
+    let has_session = run_tmux(
+        Command::new("tmux")
+            .arg("has-session")
+            .arg("-t")
+            .arg(format!("={session_name}")),
+        timeout,
+    )?
+    .status
+    .success();
+
+    let mut create_command = Command::new("tmux");
+    if has_session {
+        status!(
+            setup.print_session,
+            "Creating tmux window '{target}' in session '{session_name}'..."
+        );
+        create_command
+            .arg("new-window")
+            .arg("-d")
+            .arg("-t")
+            .arg(format!("{session_name}:"));
+    } else {
+        status!(
+            setup.print_session,
+            "Creating tmux session '{session_name}' with window '{target}'..."
+        );
+        create_command
+            .arg("new-session")
+            .arg("-d")
+            .arg("-s")
+            .arg(session_name);
+    }
+    let create_result = run_tmux(
+        create_command
+            .arg("-n")
+            .arg(sanitize_tmux_name(setup.branch_name))
+            .arg("-c")
+            .arg(target_dir.to_string_lossy().to_string()),
+        timeout,
+    )?;
+
+    if !create_result.status.success() {
+        let stderr = String::from_utf8_lossy(&create_result.stderr);
+        if setup.best_effort {
+            errors.push(format!("Failed to create tmux window: {}", stderr.trim()));
+            return Ok(None);
+        }
+        eprintln!("Failed to create tmux window. stderr: {stderr}");
+        return Err("Failed to create tmux window".into());
+    }
+
+    set_window_label(&target, setup.label, timeout);
+    send_init_commands(
+        &target,
+        processed_commands,
+        setup.best_effort,
+        timeout,
+        errors,
+    )?;
+
+    if !setup.detach {
+        if let Some(command) = setup.on_attach_command {
+            let command = expand_branch_placeholders(command, setup.branch_name, target_dir);
+            send_on_attach_command(&target, &command, timeout);
+        }
+
+        let focus = if in_tmux {
+            "switch-client"
+        } else {
+            "select-window"
+        };
+        let result = run_tmux_checked(
+            Command::new("tmux").arg(focus).arg("-t").arg(&target),
+            timeout,
+        );
+        tolerate_tmux_error(result, setup.best_effort, errors)?;
+    }
+    Ok(Some(target))
+}
+
+// Outside tmux, attaching blocks until the user detaches, so it is left out of
+// setup_tmux_environment for the caller to run once everything else is done
+fn attach_outside_tmux(setup: &TmuxSetup, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if setup.detach || std::env::var("TMUX").is_ok() {
+        return Ok(());
+    }
+    // A repo-session target is session:window, with the window already selected
+    let session_name = name.split_once(':').map_or(name, |(session, _)| session);
+    status!(
+        setup.print_session,
+        "Attaching to tmux session '{session_name}'..."
+    );
+    Command::new("tmux")
+        .arg("attach-session")
+        .arg("-t")
+        .arg(session_name)
+        .status()?;
+    Ok(())
+}
+
+fn setup_and_attach(
+    setup: &TmuxSetup,
+    errors: &mut Vec<String>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let name = setup_tmux_environment(setup, errors)?;
+    if let Some(name) = &name {
+        attach_outside_tmux(setup, name)?;
+    }
+    Ok(name)
+}
+
+// @@args goes last so arguments containing @@branch or @@dir are sent verbatim
+fn expand_init_commands(setup: &TmuxSetup) -> Result<String, Box<dyn std::error::Error>> {
+    let init_commands = filter_once_commands(setup.init_commands, setup.first_creation);
+    substitute_args(
+        &expand_branch_placeholders(&init_commands, setup.branch_name, setup.target_dir),
+        &setup.args.join(" "),
+    )
+}
+
+fn window_index_in_use(index: u32) -> bool {
+    Command::new("tmux")
+        .arg("list-windows")
+        .arg("-F")
+        .arg("#{window_index}")
+        .output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line == index.to_string())
+        })
+}
+
+// `new-window -t :N` fails when N is taken, so the window is then inserted before
+// the one at N, which moves it and the windows after it up by one
+fn window_index_args(index: u32, in_use: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    if in_use {
+        args.push("-b".to_string());
+    }
+    args.push("-t".to_string());
+    args.push(format!(":{index}"));
+    args
+}
+
+fn setup_tmux_environment(
+    setup: &TmuxSetup,
+    errors: &mut Vec<String>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if !check_tmux_available() {
+        eprintln!("Warning: tmux is not installed. Skipping tmux setup.");
+        eprintln!("To use tmux integration, please install tmux.");
+        return Ok(None);
+    }
+
+    let repo_prefix = setup.repo_prefix;
+    let target_dir = setup.target_dir;
+    let best_effort = setup.best_effort;
+    let timeout = setup.command_timeout;
+
+    let processed_commands = expand_init_commands(setup)?;
+
+    let in_tmux = std::env::var("TMUX").is_ok();
+    let can_attach = in_tmux || setup.detach || std::io::stdin().is_terminal();
+    let kind = planned_tmux_kind(setup.mode, in_tmux, can_attach);
+
+    if kind == TmuxKind::RepoSession {
+        setup_repo_session(setup, &processed_commands, in_tmux, errors)
+    } else if kind == TmuxKind::Window {
+        let window_name = tmux_target_name(repo_prefix, setup.separator, setup.branch_name);
+
+        status!(
+            setup.print_session,
+            "Creating new tmux window '{window_name}' in current session..."
+        );
+        if let Some(renumber) = setup.renumber_windows {
+            let result = run_tmux_checked(
+                Command::new("tmux")
+                    .arg("set-option")
+                    .arg("renumber-windows")
+                    .arg(if renumber { "on" } else { "off" }),
+                timeout,
+            );
+            tolerate_tmux_error(result, best_effort, errors)?;
+        }
+
+        let mut new_window = Command::new("tmux");
+        new_window.arg("new-window");
+        if let Some(index) = setup.window_index {
+            new_window.args(window_index_args(index, window_index_in_use(index)));
+        }
+        let create_window = run_tmux(
+            new_window
+                .arg("-n")
+                .arg(&window_name)
+                .arg("-c")
+                .arg(target_dir.to_string_lossy().to_string()),
+            timeout,
+        )?;
+
+        if !create_window.status.success() {
+            let stderr = String::from_utf8_lossy(&create_window.stderr);
+            if best_effort {
+                errors.push(format!("Failed to create tmux window: {}", stderr.trim()));
+                return Ok(None);
+            }
+            eprintln!("Failed to create tmux window. stderr: {stderr}");
+            return Err("Failed to create tmux window".into());
+        }
+
+        set_window_label(&window_name, setup.label, timeout);
+        send_init_commands(
+            &window_name,
+            &processed_commands,
+            best_effort,
+            timeout,
+            errors,
+        )?;
+
+        let result = run_tmux_checked(
+            Command::new("tmux")
+                .arg("select-window")
+                .arg("-t")
+                .arg(&window_name),
+            timeout,
+        );
+        tolerate_tmux_error(result, best_effort, errors)?;
+
+        if let Some(command) = setup.on_attach_command {
+            let command = expand_branch_placeholders(command, setup.branch_name, target_dir);
+            send_on_attach_command(&window_name, &command, timeout);
+        }
+
+        status!(
+            setup.print_session,
+            "✓ Switched to new window '{window_name}'"
+        );
+        Ok(Some(window_name))
+    } else if kind == TmuxKind::Session {
+        let session_name = tmux_target_name(repo_prefix, setup.separator, setup.branch_name);
+
+        status!(
+            setup.print_session,
+            "Creating tmux session '{}' in directory '{}'",
+            session_name,
+            target_dir.display()
+        );
+        let session_group = setup.session_group.filter(|group| {
+            let supported = session_groups_supported();
+            if !supported {
+                eprintln!(
+                    "Warning: session groups need tmux {}.{} or later. Creating '{session_name}' without joining '{group}'.",
+                    MIN_SESSION_GROUP_VERSION.0, MIN_SESSION_GROUP_VERSION.1
+                );
+            }
+            supported
+        });
+        let create_result = match session_group {
+            Some(group) => create_grouped_session(&session_name, group, target_dir, timeout)?,
+            None => run_tmux(
+                Command::new("tmux")
+                    .arg("new-session")
+                    .arg("-d")
+                    .arg("-s")
+                    .arg(&session_name)
+                    .arg("-c")
+                    .arg(target_dir.to_string_lossy().to_string()),
+                timeout,
+            )?,
+        };
+
+        if !create_result.status.success() {
+            let stderr = String::from_utf8_lossy(&create_result.stderr);
+            if best_effort {
+                errors.push(format!("Failed to create tmux session: {}", stderr.trim()));
+                return Ok(None);
+            }
+            eprintln!("Failed to create tmux session. stderr: {stderr}");
+            return Err("Failed to create tmux session".into());
+        }
+
+        set_window_label(&session_name, setup.label, timeout);
+        send_init_commands(
+            &session_name,
+            &processed_commands,
+            best_effort,
+            timeout,
+            errors,
+        )?;
+
+        // Leave the session detached so the caller can attach by name later
+        if !setup.detach {
+            if let Some(command) = setup.on_attach_command {
+                let command = expand_branch_placeholders(command, setup.branch_name, target_dir);
+                send_on_attach_command(&session_name, &command, timeout);
+            }
+
+            // attach-session refuses to nest, so switch the current client instead.
+            // Outside tmux the caller attaches, see attach_outside_tmux
+            if in_tmux {
+                status!(
+                    setup.print_session,
+                    "Switching to tmux session '{session_name}'..."
+                );
+                let result = run_tmux_checked(
+                    Command::new("tmux")
+                        .arg("switch-client")
+                        .arg("-t")
+                        .arg(&session_name),
+                    timeout,
+                );
+                tolerate_tmux_error(result, best_effort, errors)?;
+            }
+        }
+        Ok(Some(session_name))
+    } else {
+        status!(
+            setup.print_session,
+            "Not in a terminal environment. Navigate to {} to start working.",
+            target_dir.display()
+        );
+        Ok(None)
+    }
+}
+
+fn init_commands_for<'a>(config: &'a Config, alias: Option<&str>) -> &'a str {
+    alias
+        .and_then(|alias| config.branch_aliases.get(alias))
+        .and_then(BranchAlias::init_commands)
+        .unwrap_or(&config.settings.tmux_window_init_commands)
+}
+
+const REQUIRED_ARGS_ERROR: &str =
+    "The init commands require arguments (@@args!). Pass them after --";
+
+// `@@args!` requires arguments, while `@@args?` and `@@args` may expand to nothing
+pub fn substitute_args(commands: &str, args: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if args.is_empty() && commands.contains("@@args!") {
+        return Err(REQUIRED_ARGS_ERROR.into());
+    }
+
+    Ok(commands
+        .replace("@@args!", args)
+        .replace("@@args?", args)
+        .replace("@@args", args))
+}
+
+pub fn render_init_commands(
+    config: &Config,
+    options: &CreateOptions,
+    args: &[String],
+    alias: Option<&str>,
+    branch_name: &str,
+    target_dir: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut init_commands = init_commands_for(config, alias).to_string();
+    if options.template_engine || config.settings.init_commands_template_engine {
+        let vars = HashMap::from([
+            ("args", args.join(" ")),
+            ("branch", branch_name.to_string()),
+            ("dir", target_dir.to_string_lossy().to_string()),
+        ]);
+        init_commands = template::render(&init_commands, &vars)?;
+    }
+    if options.quiet_tmux || config.settings.init_commands_space_prefix {
+        init_commands = prefix_with_space(&init_commands);
+    }
+    Ok(init_commands)
+}
+
+// Opens a session/window for an existing copy, e.g. after its session was closed
+pub fn open_tmux_session(
+    config: &Config,
+    branch_name: &str,
+    target_dir: &Path,
+    init_commands: &str,
+    args: &[String],
+    label: Option<&str>,
+    session_group: Option<&str>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let repo_prefix = get_repo_prefix(config);
+    let tmux_setup = reopen_setup(
+        config,
+        &repo_prefix,
+        branch_name,
+        target_dir,
+        init_commands,
+        args,
+        label,
+        session_group,
+    );
+    setup_and_attach(&tmux_setup, &mut Vec::new())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn reopen_setup<'a>(
+    config: &'a Config,
+    repo_prefix: &'a str,
+    branch_name: &'a str,
+    target_dir: &'a Path,
+    init_commands: &'a str,
+    args: &'a [String],
+    label: Option<&'a str>,
+    session_group: Option<&'a str>,
+) -> TmuxSetup<'a> {
+    TmuxSetup {
+        // A copy created with --session-group rejoins its group
+        mode: if session_group.is_some() {
+            TmuxMode::Session
+        } else {
+            config.settings.tmux_mode
+        },
+        repo_prefix,
+        branch_name,
+        target_dir,
+        init_commands,
+        args,
+        separator: &config.settings.name_separator,
+        best_effort: false,
+        print_session: false,
+        detach: config.settings.create_session_without_tty && !std::io::stdin().is_terminal(),
+        on_attach_command: config.settings.on_attach_command.as_deref(),
+        window_index: config.settings.tmux_window_index,
+        renumber_windows: config.settings.tmux_renumber_windows,
+        label,
+        session_group,
+        command_timeout: config
+            .settings
+            .tmux_command_timeout_secs
+            .map(Duration::from_secs),
+        first_creation: false,
+    }
+}
+
+// `create --no-reuse` for a copy that is already open: the copy exists, so the
+// window/session is opened like switch does, without the once-only init commands
+#[allow(clippy::too_many_arguments)]
+fn reopen_tmux_target(
+    config: &Config,
+    options: &CreateOptions,
+    args: &[String],
+    alias: Option<&str>,
+    branch_name: &str,
+    target_dir: &Path,
+    mode: TmuxMode,
+    detach: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let init_commands =
+        render_init_commands(config, options, args, alias, branch_name, target_dir)?;
+    let repo_prefix = get_repo_prefix(config);
+    let mut setup = reopen_setup(
+        config,
+        &repo_prefix,
+        branch_name,
+        target_dir,
+        &init_commands,
+        args,
+        options.label.as_deref(),
+        None,
+    );
+    setup.mode = mode;
+    setup.detach = detach;
+    setup.print_session = options.print_session;
+    setup_and_attach(&setup, &mut Vec::new())
+}
+
+// rsync has no equivalent of gitignore's `!` re-includes, so those are skipped
+fn parse_gitignore(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .map(str::to_string)
+        .collect()
+}
+
+// -a is -rlptgoD; disabled components are dropped from the spelled-out set
+fn rsync_archive_flags(config: &Config) -> String {
+    let settings = &config.settings;
+    if settings.rsync_preserve_perms
+        && settings.rsync_preserve_owner
+        && settings.rsync_preserve_group
+    {
+        return "-a".to_string();
+    }
+
+    let mut flags = "-rl".to_string();
+    if settings.rsync_preserve_perms {
+        flags.push('p');
+    }
+    flags.push('t');
+    if settings.rsync_preserve_group {
+        flags.push('g');
+    }
+    if settings.rsync_preserve_owner {
+        flags.push('o');
+    }
+    flags.push('D');
+    flags
+}
+
+// A bare directory pattern only matches the directory itself, and with the trailing
+// `--exclude '*'` nothing inside it would be copied. Paths are relative to the source
+fn only_include_pattern(pattern: &str) -> String {
+    if pattern.ends_with("/***") {
+        return pattern.to_string();
+    }
+    if let Some(dir) = pattern.strip_suffix('/') {
+        return format!("{dir}/***");
+    }
+    let is_glob = pattern.contains(['*', '?', '[']);
+    if !is_glob && Path::new(pattern).is_dir() {
+        return format!("{pattern}/***");
+    }
+    pattern.to_string()
+}
+
+pub fn build_rsync_command(config: &Config, options: &CreateOptions) -> Command {
+    let mut rsync_command = Command::new("rsync");
+    // The rsync_preserve_* settings apply to a configured -a
+    for flag in &config.settings.rsync_flags {
+        if flag == "-a" {
+            rsync_command.arg(rsync_archive_flags(config));
+        } else {
+            rsync_command.arg(flag);
+        }
+    }
+
+    if options.debug {
+        rsync_command.arg("-v");
+    }
+
+    if let Some(timeout) = options.rsync_timeout.or(config.settings.rsync_timeout) {
+        rsync_command.arg(format!("--timeout={timeout}"));
+    }
+
+    if let Some(max_size) = &config.settings.rsync_max_file_size {
+        rsync_command.arg(format!("--max-size={max_size}"));
+    }
+
+    // The archive flags imply --times, so it has to be turned off explicitly
+    if !options
+        .preserve_mtime
+        .unwrap_or(config.settings.rsync_preserve_times)
+    {
+        rsync_command.arg("--no-times");
+    }
+
+    // rsync applies the first matching rule, so excludes must come before
+    // any --only includes to keep excluded paths out of a partial copy.
+
+    // Always exclude repo_sync_path
+    rsync_command
+        .arg("--exclude")
+        .arg(&config.settings.repo_sync_path);
+
+    // Forced includes win over the excludes below because they match first
+    for pattern in &options.copy_ignored {
+        rsync_command.arg("--include").arg(pattern);
+    }
+
+    // Add user-defined excludes
+    for exclude in &config.settings.rsync_excludes {
+        rsync_command.arg("--exclude").arg(exclude);
+    }
+
+    if config.settings.use_gitignore {
+        if let Ok(content) = fs::read_to_string(".gitignore") {
+            for exclude in parse_gitignore(&content) {
+                rsync_command.arg("--exclude").arg(exclude);
+            }
+        }
+    }
+
+    if !options.only.is_empty() {
+        // .git is always copied so the branch is created in the copy, not the source
+        rsync_command.arg("--include").arg(".git/***");
+        for pattern in &options.only {
+            rsync_command
+                .arg("--include")
+                .arg(only_include_pattern(pattern));
+        }
+        // Descend into every directory, drop the ones left empty, then skip the rest
+        rsync_command
+            .arg("--include")
+            .arg("*/")
+            .arg("--exclude")
+            .arg("*")
+            .arg("--prune-empty-dirs");
+    }
+
+    rsync_command
+}
+
+fn list_base_refs() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .arg("for-each-ref")
+        .arg("--format=%(refname:short)")
+        .arg("refs/heads")
+        .arg("refs/tags")
+        .output()?;
+
+    if !output.status.success() {
+        eprintln!(
+            "Failed to list git refs. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err("Failed to list git refs".into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+fn select_base_ref_with_skim() -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let refs = list_base_refs()?;
+    if refs.is_empty() {
+        return Err("No local branches or tags to choose a base from".into());
+    }
+
+    let options = SkimOptionsBuilder::default()
+        .height("50%".to_string())
+        .prompt("Select base> ".to_string())
+        .layout("reverse".to_string())
+        .build()
+        .unwrap();
+
+    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+    for git_ref in refs {
+        let _ = tx.send(Arc::new(git_ref) as Arc<dyn SkimItem>);
+    }
+    drop(tx);
+
+    Ok(Skim::run_with(&options, Some(rx))
+        .filter(|output| !output.is_abort)
+        .and_then(|output| {
+            output
+                .selected_items
+                .first()
+                .map(|item| item.output().to_string())
+        }))
+}
+
+fn create_branch_only(
+    config: &Config,
+    branch: &str,
+    expanded_branch: &str,
+    base_ref: Option<&str>,
+    group: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ulid = generate_id(config.settings.id_scheme);
+    let current_dir = std::env::current_dir()?;
+    let hook_context = HookContext {
+        branch: expanded_branch,
+        dir: &current_dir,
+        ulid: &ulid,
+    };
+    hooks::run_hooks(config, "pre-create", &hook_context)?;
+
+    let checkout_result = Command::new("git")
+        .arg("checkout")
+        .arg("-b")
+        .arg(expanded_branch)
+        .args(base_ref)
+        .output()?;
+
+    if !checkout_result.status.success() {
+        eprintln!(
+            "Failed to create git branch. stderr: {}",
+            String::from_utf8_lossy(&checkout_result.stderr)
+        );
+        return Err("Failed to create git branch".into());
+    }
+
+    let metadata = RepositoryMetadata {
+        ulid: Some(ulid.clone()),
+        id_scheme: Some(config.settings.id_scheme),
+        branch: expanded_branch.to_string(),
+        created_at: Utc::now(),
+        branch_only: true,
+        repo_name: Some(get_source_repo_name()),
+        source_path: std::env::current_dir()
+            .ok()
+            .map(|dir| dir.to_string_lossy().to_string()),
+        group,
+        ..Default::default()
+    };
+    write_metadata(config, &ulid, &metadata)?;
+    hooks::run_hooks(config, "post-create", &hook_context)?;
+
+    println!("Branch created in the current repository:");
+    println!("  Branch: {branch} -> {expanded_branch}");
+    println!("  ULID: {ulid}");
+
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct CreateOptions {
+    pub debug: bool,
+    pub link_config: bool,
+    pub rsync_timeout: Option<u64>,
+    pub only: Vec<String>,
+    pub copy_ignored: Vec<String>,
+    pub list_files: bool,
+    pub branch_only: bool,
+    pub preserve_mtime: Option<bool>,
+    pub continue_on_tmux_error: bool,
+    pub quiet_tmux: bool,
+    pub template_engine: bool,
+    pub print_session: bool,
+    pub rsync_dry_then_confirm: bool,
+    pub yes: bool,
+    pub detach: bool,
+    pub group: Option<String>,
+    pub watch: bool,
+    pub window_index: Option<u32>,
+    pub from_pr: Option<u64>,
+    pub timings: bool,
+    pub dry_run: bool,
+    pub pick_base: bool,
+    pub no_sanitize_dir: bool,
+    pub label: Option<String>,
+    pub resume: bool,
+    pub tmux_mode: Option<TmuxMode>,
+    pub session_group: Option<String>,
+    pub force: bool,
+    pub no_reuse: bool,
+    pub dir_prefix: Option<String>,
+    pub no_tmux: bool,
+    pub mirror_submodules: bool,
+}
+
+#[derive(Default)]
+struct CreateTimings {
+    rsync: Duration,
+    git: Duration,
+    tmux: Duration,
+    total: Duration,
+}
+
+impl CreateTimings {
+    fn report(&self) -> Vec<String> {
+        [
+            ("rsync", self.rsync),
+            ("git", self.git),
+            ("tmux", self.tmux),
+            ("total", self.total),
+        ]
+        .iter()
+        .map(|(label, duration)| format!("  {label:<6}{:.2}s", duration.as_secs_f64()))
+        .collect()
+    }
+}
+
+// Global defaults come first so CLI args can follow up on them
+fn merge_default_args(default_args: &[String], args: &[String]) -> Vec<String> {
+    default_args.iter().chain(args).cloned().collect()
+}
+
+pub fn create_repo(
+    branch: &str,
+    args: &[String],
+    options: &CreateOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let started_at = Instant::now();
+    let mut timings = CreateTimings::default();
+    let debug = options.debug;
+    let mut config = crate::config::load()?;
+    if config.settings.rsync_flags.is_empty() {
+        return Err("rsync_flags must contain at least one flag, e.g. [\"-a\"]".into());
+    }
+
+    let args = merge_default_args(&config.settings.global_default_args, args);
+    let args = args.as_slice();
+
+    // PR head branches already exist upstream, so they bypass alias expansion
+    let (branch, expanded_branch, alias) = match options.from_pr {
+        Some(pr_number) => {
+            let head_branch = get_pr_head_branch(pr_number)?;
+            (head_branch.clone(), head_branch, None)
+        }
+        None => {
+            let expansion = resolve_alias(branch, &config);
+            (branch.to_string(), expansion.branch, expansion.alias)
+        }
+    };
+    let branch = branch.as_str();
+    if !options.force {
+        validate_branch_name(&expanded_branch)?;
+    }
+    let ulid_string = generate_id(config.settings.id_scheme);
+    let dir_prefix = match (&options.dir_prefix, &config.settings.dir_prefix_template) {
+        (Some(prefix), _) => sanitize_dir_prefix(prefix),
+        (None, Some(template)) => render_dir_prefix(template, &ulid_string),
+        (None, None) => String::new(),
+    };
+    let directory_name =
+        copy_directory_name(&dir_prefix, &expanded_branch, options.no_sanitize_dir);
+
+    if debug {
+        eprintln!("Debug: Branch alias expansion: {branch} -> {expanded_branch}");
+        eprintln!("Debug: Directory name: {directory_name}");
+        eprintln!("Debug: Effective args: {args:?}");
+    }
+
+    let base_ref = if options.pick_base {
+        let Some(base_ref) = select_base_ref_with_skim()? else {
+            status!(options.print_session, "Creation cancelled.");
+            return Ok(());
+        };
+        Some(base_ref)
+    } else {
+        None
+    };
+
+    if options.branch_only {
+        return create_branch_only(
+            &config,
+            branch,
+            &expanded_branch,
+            base_ref.as_deref(),
+            options.group.clone(),
+        );
+    }
+
+    // Checked before copying so a missing argument doesn't leave a copy behind
+    if !options.no_tmux
+        && args.is_empty()
+        && init_commands_for(&config, alias.as_deref()).contains("@@args!")
+    {
+        return Err(REQUIRED_ARGS_ERROR.into());
+    }
+
+    let tmux_mode = options.tmux_mode.unwrap_or(config.settings.tmux_mode);
+    let detach = options.detach
+        || options.print_session
+        || options.watch
+        || (config.settings.create_session_without_tty && !std::io::stdin().is_terminal());
+    let in_tmux = std::env::var("TMUX").is_ok();
+    let tmux_kind = if options.no_tmux {
+        TmuxKind::None
+    } else {
+        planned_tmux_kind(
+            tmux_mode,
+            in_tmux,
+            in_tmux || detach || std::io::stdin().is_terminal(),
+        )
+    };
+    // The configured group only applies when the copy gets a session of its own,
+    // while --session-group forces one
+    let session_group = options.session_group.clone().or_else(|| {
+        config
+            .settings
+            .tmux_session_group
+            .clone()
+            .filter(|_| tmux_kind == TmuxKind::Session)
+    });
+
+    let target_dir = PathBuf::from(&config.settings.repo_sync_path).join(&directory_name);
+    let use_worktree = config.settings.copy_strategy == CopyStrategy::Worktree;
+    // Running create again for a copy that is still open switches to it
+    if target_dir.exists()
+        && !options.dry_run
+        && !options.no_tmux
+        && read_in_progress_marker(&target_dir).is_none()
+    {
+        if let Some((name, is_window)) =
+            find_tmux_session_or_window(&config, &expanded_branch, None)
+        {
+            let name = if options.no_reuse {
+                status!(
+                    options.print_session,
+                    "Reopening the tmux {} '{name}'",
+                    if is_window { "window" } else { "session" }
+                );
+                close_tmux_target(&name, is_window)?;
+                reopen_tmux_target(
+                    &config,
+                    options,
+                    args,
+                    alias.as_deref(),
+                    &expanded_branch,
+                    &std::env::current_dir()?.join(&target_dir),
+                    tmux_mode,
+                    detach,
+                )?
+            } else {
+                reuse_tmux_target(&name, is_window, detach, options.print_session)?;
+                Some(name)
+            };
+            if options.print_session {
+                if let Some(name) = name {
+                    println!("{name}");
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    let interrupted_ulid = if target_dir.exists() {
+        match read_in_progress_marker(&target_dir) {
+            Some(_) if !options.resume => {
+                return Err(format!(
+                    "Directory '{}' holds an interrupted copy. Run the same command with --resume to continue it, or delete the directory.",
+                    target_dir.display()
+                )
+                .into());
+            }
+            Some(_) if use_worktree => {
+                return Err("--resume only applies to copy_strategy = \"rsync\"".into());
+            }
+            Some(ulid) => Some(ulid),
+            None => {
+                return Err(format!(
+                    "Directory '{}' already exists. Use a different branch name or delete the existing one first.",
+                    target_dir.display()
+                ).into());
+            }
+        }
+    } else {
+        None
+    };
+
+    let current_dir = std::env::current_dir()?;
+
+    if options.dry_run {
+        let rsync_command = build_rsync_command(&config, options);
+        let rsync_args: Vec<String> = rsync_command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        println!("Dry run (nothing was created):");
+        println!("  Branch: {branch} -> {expanded_branch}");
+        println!("  Target: {}", current_dir.join(&target_dir).display());
+        if interrupted_ulid.is_some() {
+            println!("  Resume: the interrupted copy in the target is continued");
+        }
+        if use_worktree {
+            println!(
+                "  git: git worktree add -b {expanded_branch} {}",
+                current_dir.join(&target_dir).display()
+            );
+        } else {
+            println!(
+                "  rsync: rsync {} {}/ {}/",
+                rsync_args.join(" "),
+                current_dir.display(),
+                target_dir.display()
+            );
+        }
+        println!(
+            "  tmux: {}",
+            tmux_target_name(
+                &get_repo_prefix(&config),
+                &config.settings.name_separator,
+                &expanded_branch
+            )
+        );
+        return Ok(());
+    }
+
+    if options.list_files {
+        let list_result = build_rsync_command(&config, options)
+            .arg("-n")
+            .arg("--out-format=%n")
+            .arg(format!("{}/", current_dir.display()))
+            .arg(format!("{}/", target_dir.display()))
+            .status()?;
+
+        if !list_result.success() {
+            return Err("rsync failed".into());
+        }
+        return Ok(());
+    }
+
+    if config.settings.check_git_state {
+        if let Some(operation) = get_git_dir(&current_dir).and_then(|d| detect_git_operation(&d)) {
+            return Err(format!(
+                "The source repository has a {operation} in progress. Finish or abort it before creating a copy."
+            )
+            .into());
+        }
+    }
+
+    // `git checkout -b` would fail on the branch the source has checked out, after the copy
+    let stay_on_branch = options.from_pr.is_none()
+        && current_branch(&current_dir).as_deref() == Some(expanded_branch.as_str());
+    if stay_on_branch {
+        if use_worktree {
+            return Err(format!(
+                "'{expanded_branch}' is checked out in the source repository, and git worktree can't check it out again. Use a different branch name."
+            )
+            .into());
+        }
+        if config.settings.current_branch_policy == CurrentBranchPolicy::Error {
+            return Err(format!(
+                "'{expanded_branch}' is the branch checked out in the source repository. Use a different branch name, or set current_branch_policy = \"stay\" to copy it and stay on it."
+            )
+            .into());
+        }
+        status!(
+            options.print_session,
+            "'{expanded_branch}' is checked out in the source, so the copy stays on it"
+        );
+    }
+
+    let source_dirty = is_working_tree_dirty(&current_dir, &config.settings.dirty_ignore);
+    if source_dirty
+        && config.settings.dirty_tree_policy == DirtyTreePolicy::Warn
+        && !options.yes
+        && !confirm("The working tree has uncommitted changes that will be copied. Continue?")?
+    {
+        status!(options.print_session, "Creation cancelled.");
+        return Ok(());
+    }
+
+    if let Some(threshold_mb) = config.settings.warn_large_dirs_mb {
+        let mut excludes = config.settings.rsync_excludes.clone();
+        excludes.push(config.settings.repo_sync_path.clone());
+        let mut added_excludes = Vec::new();
+        for (dir, size) in find_large_dirs(&current_dir, threshold_mb * 1024 * 1024, &excludes) {
+            eprintln!(
+                "Warning: '{dir}' is {} MB and not excluded. Consider adding it to rsync_excludes.",
+                size / (1024 * 1024)
+            );
+            if !options.yes && confirm(&format!("Exclude '{dir}' from this copy?"))? {
+                added_excludes.push(dir);
+            }
+        }
+
+        if !added_excludes.is_empty() {
+            config
+                .settings
+                .rsync_excludes
+                .extend(added_excludes.iter().cloned());
+            if confirm("Save these excludes to rsync_excludes in the config file?")? {
+                let config_path = crate::config::add_rsync_excludes(&added_excludes)?;
+                eprintln!("Updated {}", config_path.display());
+            }
+        }
+    }
+
+    if options.rsync_dry_then_confirm {
+        let dry_run = build_rsync_command(&config, options)
+            .arg("-n")
+            .arg("--stats")
+            .arg(format!("{}/", current_dir.display()))
+            .arg(format!("{}/", target_dir.display()))
+            .output()?;
+
+        if !dry_run.status.success() {
+            eprintln!(
+                "rsync dry run failed. stderr: {}",
+                String::from_utf8_lossy(&dry_run.stderr)
+            );
+            return Err("rsync dry run failed".into());
+        }
+
+        let (files, size) = parse_rsync_stats(&String::from_utf8_lossy(&dry_run.stdout));
+        eprintln!("rsync would copy {files} files ({size} bytes)");
+
+        if !options.yes && !confirm("Continue with the copy?")? {
+            status!(options.print_session, "Creation cancelled.");
+            return Ok(());
+        }
+    }
+
+    let absolute_target_dir = current_dir.join(&target_dir);
+    let hook_context = HookContext {
+        branch: &expanded_branch,
+        dir: &absolute_target_dir,
+        ulid: &ulid_string,
+    };
+    hooks::run_hooks(&config, "pre-create", &hook_context)?;
+    hooks::run_pre_create_command(&config, &hook_context, args)?;
+
+    // The interrupted attempt's metadata is replaced by this one
+    if let Some(interrupted_ulid) = &interrupted_ulid {
+        let _ = fs::remove_file(metadata_file_path(
+            &config,
+            interrupted_ulid,
+            &directory_name,
+        )?);
+        status!(
+            options.print_session,
+            "Resuming the interrupted copy in '{}'",
+            target_dir.display()
+        );
+    }
+
+    let mut metadata = RepositoryMetadata {
+        ulid: Some(ulid_string.clone()),
+        id_scheme: Some(config.settings.id_scheme),
+        branch: expanded_branch.clone(),
+        created_at: Utc::now(),
+        directory: Some(directory_name.clone()),
+        // Resolved before rsync so it reflects the source, not the copy after checkout
+        source_commit: get_head_commit(&current_dir),
+        repo_name: Some(get_source_repo_name()),
+        source_path: Some(current_dir.to_string_lossy().to_string()),
+        group: options.group.clone(),
+        pr_number: options.from_pr,
+        args: args.to_vec(),
+        worktree: use_worktree,
+        label: options.label.clone(),
+        alias: alias.clone(),
+        session_group: session_group.clone(),
+        template_engine: options.template_engine,
+        quiet_tmux: options.quiet_tmux,
+        tmux_kind: Some(tmux_kind),
+        ..Default::default()
+    };
+    let ulid_file_path = write_metadata(&config, &ulid_string, &metadata)?;
+    let mut rollback = CopyRollback {
+        metadata_path: ulid_file_path.clone(),
+        target_dir: absolute_target_dir.clone(),
+        worktree: use_worktree,
+        worktree_branch: None,
+        keep_interrupted_copy: options.resume,
+        armed: true,
+    };
+
+    if use_worktree {
+        let worktree_started_at = Instant::now();
+        let mut worktree_command = Command::new("git");
+        worktree_command.arg("worktree").arg("add");
+        // A PR branch is checked out by gh below, so the worktree starts detached
+        if options.from_pr.is_some() {
+            worktree_command.arg("--detach");
+        } else {
+            worktree_command.arg("-b").arg(&expanded_branch);
+        }
+        let worktree_result = worktree_command
+            .arg(&absolute_target_dir)
+            .args(&base_ref)
+            .output()?;
+        timings.git = worktree_started_at.elapsed();
+
+        if !worktree_result.status.success() {
+            eprintln!(
+                "Failed to create git worktree. stderr: {}",
+                String::from_utf8_lossy(&worktree_result.stderr)
+            );
+            return Err("Failed to create git worktree".into());
+        }
+        if options.from_pr.is_none() {
+            rollback.worktree_branch = Some(expanded_branch.clone());
+        }
+    } else {
+        fs::create_dir_all(&target_dir)?;
+        fs::write(target_dir.join(IN_PROGRESS_MARKER), &ulid_string)?;
+
+        let mut rsync_command = build_rsync_command(&config, options);
+        if options.print_session {
+            rsync_command.stdout(io::stderr());
+        }
+        let rsync_started_at = Instant::now();
+        let rsync_result = rsync_command
+            .arg(format!("{}/", current_dir.display()))
+            .arg(format!("{}/", target_dir.display()))
+            .status()?;
+        timings.rsync = rsync_started_at.elapsed();
+
+        if !rsync_result.success() {
+            if options.resume {
+                eprintln!(
+                    "The partial copy was kept in '{}'. Run the same command with --resume to continue it.",
+                    target_dir.display()
+                );
+            }
+            return Err("rsync failed".into());
+        }
+        fs::remove_file(target_dir.join(IN_PROGRESS_MARKER))?;
+    }
+
+    if options.link_config && !config.settings.link_paths.is_empty() {
+        metadata.linked_paths = link_shared_paths(
+            &current_dir,
+            &absolute_target_dir,
+            &config.settings.link_paths,
+        )?;
+        fs::write(&ulid_file_path, serde_json::to_string_pretty(&metadata)?)?;
+    }
+
+    let git_started_at = Instant::now();
+    let checkout_result = match options.from_pr {
+        // gh fetches the head branch (including from forks) and sets up tracking
+        Some(pr_number) => Some(
+            Command::new("gh")
+                .arg("pr")
+                .arg("checkout")
+                .arg(pr_number.to_string())
+                .current_dir(&absolute_target_dir)
+                .output()?,
+        ),
+        // `git worktree add -b` already created the branch
+        None if use_worktree => None,
+        // The copy is already on the source's branch
+        None if stay_on_branch => None,
+        None => Some(
+            Command::new("git")
+                .arg("checkout")
+                .arg("-b")
+                .arg(&expanded_branch)
+                .args(&base_ref)
+                .current_dir(&absolute_target_dir)
+                .output()?,
+        ),
+    };
+
+    if let Some(checkout_result) = checkout_result.filter(|result| !result.status.success()) {
+        eprintln!(
+            "Failed to create git branch. stderr: {}",
+            String::from_utf8_lossy(&checkout_result.stderr)
+        );
+        return Err("Failed to create git branch".into());
+    }
+
+    // Commit before init commands run so a `git reset --hard` can't discard the changes.
+    // A worktree starts clean, so there is nothing to commit
+    if source_dirty && !use_worktree && config.settings.dirty_tree_policy == DirtyTreePolicy::Commit
+    {
+        commit_copied_changes(&absolute_target_dir)?;
+        status!(
+            options.print_session,
+            "Committed uncommitted changes from the source as WIP on '{expanded_branch}'"
+        );
+    }
+    if options.mirror_submodules {
+        if absolute_target_dir.join(".gitmodules").exists() {
+            status!(options.print_session, "Initializing submodules...");
+            update_submodules(&absolute_target_dir)?;
+            status!(options.print_session, "Submodules initialized");
+        } else {
+            status!(
+                options.print_session,
+                "No .gitmodules in the copy, skipping submodules"
+            );
+        }
+    }
+    timings.git += git_started_at.elapsed();
+    rollback.disarm();
+
+    hooks::run_hooks(&config, "post-create", &hook_context)?;
+
+    let quiet = options.print_session;
+    status!(quiet, "Repository duplicated successfully:");
+    status!(quiet, "  Branch: {branch} -> {expanded_branch}");
+    status!(quiet, "  ULID: {ulid_string}");
+    if let Some(group) = &metadata.group {
+        status!(quiet, "  Group: {group}");
+    }
+    if let Some(pr_number) = metadata.pr_number {
+        status!(quiet, "  Pull request: #{pr_number}");
+    }
+    if let Some(label) = &metadata.label {
+        status!(quiet, "  Label: {label}");
+    }
+    status!(quiet, "  Target: {}", target_dir.display());
+    for linked_path in &metadata.linked_paths {
+        status!(quiet, "  Linked: {linked_path}");
+    }
+
+    let init_commands = if options.no_tmux {
+        String::new()
+    } else {
+        render_init_commands(
+            &config,
+            options,
+            args,
+            alias.as_deref(),
+            &expanded_branch,
+            &absolute_target_dir,
+        )?
+    };
+
+    let best_effort = options.continue_on_tmux_error || config.settings.continue_on_tmux_error;
+    let mut tmux_errors = Vec::new();
+    let repo_prefix = get_repo_prefix(&config);
+    let tmux_setup = TmuxSetup {
+        mode: tmux_mode,
+        repo_prefix: &repo_prefix,
+        branch_name: &expanded_branch,
+        target_dir: &absolute_target_dir,
+        init_commands: &init_commands,
+        args,
+        separator: &config.settings.name_separator,
+        best_effort,
+        print_session: options.print_session,
+        detach,
+        on_attach_command: config.settings.on_attach_command.as_deref(),
+        window_index: options.window_index.or(config.settings.tmux_window_index),
+        renumber_windows: config.settings.tmux_renumber_windows,
+        label: options.label.as_deref(),
+        session_group: session_group.as_deref(),
+        command_timeout: config
+            .settings
+            .tmux_command_timeout_secs
+            .map(Duration::from_secs),
+        first_creation: true,
+    };
+    let tmux_started_at = Instant::now();
+    // A window left open after its old copy was removed by hand still points at the
+    // deleted directory, so it is replaced rather than reused
+    let stale_tmux = if tmux_kind == TmuxKind::None {
+        None
+    } else {
+        find_tmux_session_or_window(&config, &expanded_branch, Some(tmux_kind))
+    };
+    if let Some((name, is_window)) = stale_tmux {
+        status!(
+            options.print_session,
+            "Closing the tmux {} '{name}' left open from an earlier copy",
+            if is_window { "window" } else { "session" }
+        );
+        let result = close_tmux_target(&name, is_window);
+        tolerate_tmux_error(result, best_effort, &mut tmux_errors)?;
+    }
+    let tmux_name = if options.no_tmux {
+        // Only the path on stdout, so scripts can capture it
+        println!("{}", absolute_target_dir.display());
+        None
+    } else {
+        match setup_tmux_environment(&tmux_setup, &mut tmux_errors) {
+            Ok(tmux_name) => tmux_name,
+            Err(e) => {
+                if !best_effort {
+                    return Err(e);
+                }
+                tmux_errors.push(e.to_string());
+                None
+            }
+        }
+    };
+    timings.tmux = tmux_started_at.elapsed();
+    timings.total = started_at.elapsed();
+
+    if !tmux_errors.is_empty() {
+        eprintln!("Warning: tmux setup finished with errors:");
+        for error in &tmux_errors {
+            eprintln!("  - {error}");
+        }
+        eprintln!(
+            "The repository copy is ready at {}",
+            absolute_target_dir.display()
+        );
+    }
+
+    if options.timings || debug {
+        eprintln!("Timings:");
+        for line in timings.report() {
+            eprintln!("{line}");
+        }
+        metadata.create_duration_ms = Some(timings.total.as_millis() as u64);
+        fs::write(&ulid_file_path, serde_json::to_string_pretty(&metadata)?)?;
+    }
+
+    // Last, since it blocks until detach; the copy may even be deleted by then
+    if let Some(tmux_name) = &tmux_name {
+        attach_outside_tmux(&tmux_setup, tmux_name)?;
+    }
+
+    if options.print_session {
+        if let Some(tmux_name) = tmux_name {
+            println!("{tmux_name}");
+        }
+    }
+
+    if options.watch {
+        watch::watch_and_sync(
+            &config,
+            &current_dir,
+            &absolute_target_dir,
+            &metadata.linked_paths,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_gitignore() {
+        let content = "# build output\n/target\n\nnode_modules/\n!keep.log\n*.log\n";
+        assert_eq!(
+            parse_gitignore(content),
+            vec!["/target", "node_modules/", "*.log"]
+        );
+    }
+
+    #[test]
+    fn test_create_timings_report() {
+        let timings = CreateTimings {
+            rsync: Duration::from_millis(1500),
+            git: Duration::from_millis(20),
+            tmux: Duration::ZERO,
+            total: Duration::from_millis(1520),
+        };
+
+        assert_eq!(
+            timings.report(),
+            vec![
+                "  rsync 1.50s",
+                "  git   0.02s",
+                "  tmux  0.00s",
+                "  total 1.52s"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branch_to_directory_name() {
+        assert_eq!(branch_to_directory_name("feature/test"), "feature-test");
+        assert_eq!(branch_to_directory_name("fix/bug/123"), "fix-bug-123");
+        assert_eq!(branch_to_directory_name("simple-branch"), "simple-branch");
+        assert_eq!(branch_to_directory_name(""), "");
+    }
+
+    #[test]
+    fn test_copy_directory_name() {
+        assert_eq!(
+            copy_directory_name("20240101-", "feature/api", false),
+            "20240101-feature-api"
+        );
+
+        let nested = Path::new(".trr").join(copy_directory_name("", "feature/api", true));
+        assert_eq!(nested, Path::new(".trr/feature/api"));
+        assert_eq!(nested.parent(), Some(Path::new(".trr/feature")));
+    }
+
+    #[test]
+    fn test_tmux_target_name() {
+        assert_eq!(
+            tmux_target_name("trr", "-", "feature/test"),
+            "trr-feature-test"
+        );
+        assert_eq!(tmux_target_name("trr", "", "main"), "trrmain");
+        assert_eq!(
+            tmux_target_name("trr", "-", "release/1.2"),
+            "trr-release-1-2"
+        );
+        assert_eq!(repo_session_target("trr", "release/1.2"), "trr:release-1-2");
+    }
+
+    #[test]
+    fn test_expand_alias_static() {
+        let mut config = Config::default();
+        config.branch_aliases.clear();
+        config
+            .branch_aliases
+            .insert("@f".to_string(), "feature".into());
+        config
+            .branch_aliases
+            .insert("@b".to_string(), "bugfix".into());
+
+        assert_eq!(resolve_alias("@f/test", &config).branch, "feature/test");
+        assert_eq!(resolve_alias("@b/123", &config).branch, "bugfix/123");
+        assert_eq!(resolve_alias("@f", &config).branch, "feature");
+        assert_eq!(resolve_alias("no-alias", &config).branch, "no-alias");
+    }
+
+    #[test]
+    fn test_resolve_alias_dynamic() {
+        let mut config = Config::default();
+        config.branch_aliases.clear();
+        config
+            .branch_aliases
+            .insert("@t".to_string(), "!echo feature/20250708".into());
+
+        let expansion = resolve_alias("@t-login", &config);
+        assert_eq!(expansion.alias, Some("@t".to_string()));
+        assert!(expansion.is_command);
+        assert_eq!(expansion.expansion, Some("feature/20250708".to_string()));
+        assert_eq!(expansion.branch, "feature/20250708-login");
+
+        let expansion = resolve_alias("main", &config);
+        assert_eq!(expansion.alias, None);
+        assert_eq!(expansion.branch, "main");
+    }
+
+    #[test]
+    fn test_read_ulid_metadata_json() {
+        let temp_dir = std::env::temp_dir();
+        let test_id = Ulid::new().to_string();
+        let file_path = temp_dir.join(format!("trr_test_{test_id}.json"));
+
+        let metadata = RepositoryMetadata {
+            branch: "feature/test".to_string(),
+            created_at: Utc::now(),
+            directory: Some("feature-test".to_string()),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string_pretty(&metadata).unwrap();
         fs::write(&file_path, json).unwrap();
 
         let result = read_ulid_metadata(&file_path).unwrap();
@@ -402,6 +2546,86 @@ mod tests {
         let _ = fs::remove_file(&file_path);
     }
 
+    #[test]
+    fn test_metadata_branch_only_round_trip() {
+        let metadata = RepositoryMetadata {
+            branch: "feature/in-place".to_string(),
+            created_at: Utc::now(),
+            branch_only: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&metadata).unwrap();
+        let result: RepositoryMetadata = serde_json::from_str(&json).unwrap();
+        assert!(result.branch_only);
+        assert_eq!(result.directory, None);
+
+        let copy = RepositoryMetadata {
+            branch: "feature/copy".to_string(),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&copy).unwrap();
+        assert!(!json.contains("branch_only"));
+    }
+
+    #[test]
+    fn test_read_ulid_metadata_without_source_commit() {
+        let json = r#"{"branch":"feature/old","created_at":"2025-07-08T00:00:00Z","directory":"feature-old"}"#;
+        let metadata: RepositoryMetadata = serde_json::from_str(json).unwrap();
+        assert_eq!(metadata.branch, "feature/old");
+        assert_eq!(metadata.source_commit, None);
+        assert!(metadata.args.is_empty());
+    }
+
+    #[test]
+    fn test_window_index_args() {
+        assert_eq!(window_index_args(5, false), vec!["-t", ":5"]);
+        assert_eq!(window_index_args(1, true), vec!["-b", "-t", ":1"]);
+    }
+
+    #[test]
+    fn test_metadata_args_round_trip() {
+        let metadata = RepositoryMetadata {
+            branch: "feature/api".to_string(),
+            args: vec!["--port".to_string(), "3001".to_string()],
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&metadata).unwrap();
+        let result: RepositoryMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(result.args, vec!["--port", "3001"]);
+
+        // A recreated session substitutes the recorded args like create did
+        let mut config = Config::default();
+        config.settings.tmux_window_init_commands = "cd @@dir\nnpm run dev @@args".to_string();
+        let dir = Path::new("/tmp/copy");
+        let recreate = |config: &Config, options: &CreateOptions| {
+            let init_commands =
+                render_init_commands(config, options, &result.args, None, &result.branch, dir)
+                    .unwrap();
+            let setup = reopen_setup(
+                config,
+                "trr",
+                &result.branch,
+                dir,
+                &init_commands,
+                &result.args,
+                None,
+                None,
+            );
+            expand_init_commands(&setup).unwrap()
+        };
+        assert_eq!(
+            recreate(&config, &CreateOptions::default()),
+            "cd /tmp/copy\nnpm run dev --port 3001"
+        );
+
+        config.settings.tmux_window_init_commands = "npm run dev {{args}}".to_string();
+        let options = CreateOptions {
+            template_engine: true,
+            ..Default::default()
+        };
+        assert_eq!(recreate(&config, &options), "npm run dev --port 3001");
+    }
+
     #[test]
     fn test_read_ulid_metadata_plain_text() {
         let temp_dir = std::env::temp_dir();
@@ -418,6 +2642,377 @@ mod tests {
         let _ = fs::remove_file(&file_path);
     }
 
+    #[test]
+    fn test_link_shared_paths() {
+        let test_id = Ulid::new().to_string();
+        let base_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        let source_dir = base_dir.join("source");
+        let target_dir = base_dir.join("target");
+        fs::create_dir_all(source_dir.join("cache")).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(source_dir.join(".env"), "KEY=value").unwrap();
+        fs::write(target_dir.join(".env"), "KEY=copied").unwrap();
+
+        let link_paths = vec![
+            ".env".to_string(),
+            "cache/".to_string(),
+            "missing".to_string(),
+        ];
+        let linked = link_shared_paths(&source_dir, &target_dir, &link_paths).unwrap();
+
+        assert_eq!(linked, vec![".env".to_string(), "cache".to_string()]);
+        assert!(target_dir.join(".env").is_symlink());
+        assert!(target_dir.join("cache").is_symlink());
+        assert_eq!(
+            fs::read_to_string(target_dir.join(".env")).unwrap(),
+            "KEY=value"
+        );
+
+        // Clean up
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_only_include_pattern() {
+        assert_eq!(only_include_pattern("services/api/"), "services/api/***");
+        assert_eq!(only_include_pattern("services/api/***"), "services/api/***");
+        assert_eq!(only_include_pattern("package.json"), "package.json");
+        assert_eq!(only_include_pattern("*.md"), "*.md");
+
+        // Tests run from the crate root, where src is a directory
+        assert_eq!(only_include_pattern("src"), "src/***");
+        assert_eq!(only_include_pattern("Cargo.toml"), "Cargo.toml");
+    }
+
+    #[test]
+    fn test_build_rsync_command_only() {
+        let mut config = Config::default();
+        config.settings.rsync_excludes = vec!["target".to_string()];
+        let options = CreateOptions {
+            only: vec!["services/api/***".to_string()],
+            ..Default::default()
+        };
+
+        let command = build_rsync_command(&config, &options);
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(
+            args,
+            vec![
+                "-a",
+                "--exclude",
+                ".trr",
+                "--exclude",
+                "target",
+                "--include",
+                ".git/***",
+                "--include",
+                "services/api/***",
+                "--include",
+                "*/",
+                "--exclude",
+                "*",
+                "--prune-empty-dirs",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_rsync_command_copy_ignored() {
+        let mut config = Config::default();
+        config.settings.rsync_excludes = vec![".env*".to_string()];
+        let options = CreateOptions {
+            copy_ignored: vec![".env.local".to_string()],
+            ..Default::default()
+        };
+
+        let command = build_rsync_command(&config, &options);
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(
+            args,
+            vec![
+                "-a",
+                "--exclude",
+                ".trr",
+                "--include",
+                ".env.local",
+                "--exclude",
+                ".env*",
+            ]
+        );
+
+        if Command::new("rsync").arg("--version").output().is_err() {
+            eprintln!("Skipping the rsync run: rsync is not installed");
+            return;
+        }
+
+        let test_id = Ulid::new().to_string();
+        let base_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        let source_dir = base_dir.join("source");
+        let target_dir = base_dir.join("target");
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(source_dir.join(".env"), "SECRET=1").unwrap();
+        fs::write(source_dir.join(".env.local"), "LOCAL=1").unwrap();
+        fs::write(source_dir.join("main.rs"), "fn main() {}").unwrap();
+
+        let status = build_rsync_command(&config, &options)
+            .arg(format!("{}/", source_dir.display()))
+            .arg(format!("{}/", target_dir.display()))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(target_dir.join(".env.local").exists());
+        assert!(!target_dir.join(".env").exists());
+        assert!(target_dir.join("main.rs").exists());
+
+        // Clean up
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_build_rsync_command_max_size() {
+        let mut config = Config::default();
+        let has_max_size = |config: &Config| {
+            build_rsync_command(config, &CreateOptions::default())
+                .get_args()
+                .any(|arg| arg == "--max-size=100M")
+        };
+
+        assert!(!has_max_size(&config));
+        config.settings.rsync_max_file_size = Some("100M".to_string());
+        assert!(has_max_size(&config));
+    }
+
+    #[test]
+    fn test_build_rsync_command_preserve_times() {
+        let mut config = Config::default();
+        let has_no_times = |config: &Config, options: &CreateOptions| {
+            build_rsync_command(config, options)
+                .get_args()
+                .any(|arg| arg == "--no-times")
+        };
+
+        assert!(!has_no_times(&config, &CreateOptions::default()));
+
+        config.settings.rsync_preserve_times = false;
+        assert!(has_no_times(&config, &CreateOptions::default()));
+
+        let options = CreateOptions {
+            preserve_mtime: Some(true),
+            ..Default::default()
+        };
+        assert!(!has_no_times(&config, &options));
+    }
+
+    #[test]
+    fn test_generate_id() {
+        assert!(Ulid::from_string(&generate_id(IdScheme::Ulid)).is_ok());
+
+        let uuid = generate_id(IdScheme::Uuid);
+        let groups: Vec<usize> = uuid.split('-').map(str::len).collect();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&uuid[14..15], "7");
+        assert!("89ab".contains(&uuid[19..20]));
+
+        let timestamp = generate_id(IdScheme::Timestamp);
+        let (time, suffix) = timestamp.split_once('-').unwrap();
+        assert!(chrono::NaiveDateTime::parse_from_str(time, "%Y%m%dT%H%M%S%3fZ").is_ok());
+        assert_eq!(suffix.len(), 4);
+
+        // Like ULIDs, UUIDv7s sort by creation time
+        let earlier = ulid_to_uuid_v7(Ulid::from_parts(1_000, u128::MAX));
+        let later = ulid_to_uuid_v7(Ulid::from_parts(2_000, 0));
+        assert!(earlier < later);
+    }
+
+    #[test]
+    fn test_dir_prefix() {
+        assert_eq!(sanitize_dir_prefix("20240101-"), "20240101-");
+        assert_eq!(sanitize_dir_prefix("../JIRA 12/"), "..-JIRA-12-");
+
+        let date = chrono::Local::now().format("%Y%m%d").to_string();
+        assert_eq!(
+            render_dir_prefix("{date}-{ulid}-", "01ABC"),
+            format!("{date}-01ABC-")
+        );
+        assert_eq!(render_dir_prefix("t/{ulid}_", "01ABC"), "t-01ABC_");
+    }
+
+    #[test]
+    fn test_filter_once_commands() {
+        let commands = "git reset --hard # once\nnvim\necho '# once only'\n npm ci  # once  ";
+        assert_eq!(
+            filter_once_commands(commands, true),
+            "git reset --hard\nnvim\necho '# once only'\n npm ci"
+        );
+        assert_eq!(
+            filter_once_commands(commands, false),
+            "nvim\necho '# once only'"
+        );
+    }
+
+    #[test]
+    fn test_validate_branch_name() {
+        assert!(validate_branch_name("feature/login-v1.2_fix").is_ok());
+        assert!(validate_branch_name("feature/foo bar").is_err());
+        assert!(validate_branch_name("fix\ttabs").is_err());
+        assert!(validate_branch_name("fix;rm").is_err());
+        assert!(validate_branch_name("fix$(id)").is_err());
+        assert!(validate_branch_name("").is_err());
+    }
+
+    #[test]
+    fn test_rsync_archive_flags() {
+        let mut config = Config::default();
+        assert_eq!(rsync_archive_flags(&config), "-a");
+
+        config.settings.rsync_preserve_owner = false;
+        config.settings.rsync_preserve_group = false;
+        assert_eq!(rsync_archive_flags(&config), "-rlptD");
+
+        config.settings.rsync_preserve_perms = false;
+        assert_eq!(rsync_archive_flags(&config), "-rltD");
+
+        config.settings.rsync_preserve_group = true;
+        let command = build_rsync_command(&config, &CreateOptions::default());
+        assert_eq!(command.get_args().next().unwrap(), "-rltgD");
+    }
+
+    #[test]
+    fn test_build_rsync_command_flags() {
+        let mut config = Config::default();
+        config.settings.rsync_flags = vec!["-a".to_string(), "-L".to_string(), "-H".to_string()];
+        let options = CreateOptions {
+            debug: true,
+            ..Default::default()
+        };
+
+        let command = build_rsync_command(&config, &options);
+        let args: Vec<_> = command.get_args().take(4).collect();
+        assert_eq!(args, vec!["-a", "-L", "-H", "-v"]);
+
+        config.settings.rsync_flags = vec!["-rlt".to_string()];
+        let command = build_rsync_command(&config, &CreateOptions::default());
+        assert_eq!(command.get_args().next().unwrap(), "-rlt");
+    }
+
+    #[test]
+    fn test_prefix_with_space() {
+        assert_eq!(
+            prefix_with_space("git reset --hard\n\ntmux split-window -h\n"),
+            " git reset --hard\n\n tmux split-window -h"
+        );
+        assert_eq!(prefix_with_space(""), "");
+    }
+
+    #[test]
+    fn test_parse_rsync_stats() {
+        let output = "\
+Number of files: 1,523 (reg: 1,401, dir: 122)
+Number of created files: 1,523 (reg: 1,401, dir: 122)
+Number of regular files transferred: 1,401
+Total file size: 52,428,800 bytes
+Total transferred file size: 52,428,800 bytes
+";
+        assert_eq!(parse_rsync_stats(output), (1401, 52428800));
+        assert_eq!(parse_rsync_stats(""), (0, 0));
+    }
+
+    #[test]
+    fn test_is_working_tree_dirty_with_ignore() {
+        let test_id = Ulid::new().to_string();
+        let repo_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(repo_dir.join("generated")).unwrap();
+        Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .current_dir(&repo_dir)
+            .status()
+            .unwrap();
+
+        assert!(!is_working_tree_dirty(&repo_dir, &[]));
+
+        fs::write(repo_dir.join("generated").join("schema.json"), "{}").unwrap();
+        assert!(is_working_tree_dirty(&repo_dir, &[]));
+        assert!(!is_working_tree_dirty(
+            &repo_dir,
+            &["generated".to_string()]
+        ));
+
+        fs::write(repo_dir.join("main.rs"), "fn main() {}").unwrap();
+        assert!(is_working_tree_dirty(&repo_dir, &["generated".to_string()]));
+
+        // Clean up
+        let _ = fs::remove_dir_all(&repo_dir);
+    }
+
+    #[test]
+    fn test_find_large_dirs() {
+        let test_id = Ulid::new().to_string();
+        let source_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(source_dir.join("node_modules")).unwrap();
+        fs::create_dir_all(source_dir.join("target")).unwrap();
+        fs::create_dir_all(source_dir.join("web").join("node_modules")).unwrap();
+        fs::create_dir_all(source_dir.join("src")).unwrap();
+        fs::write(
+            source_dir.join("node_modules").join("a.js"),
+            vec![0u8; 2048],
+        )
+        .unwrap();
+        fs::write(source_dir.join("target").join("bin"), vec![0u8; 2048]).unwrap();
+        fs::write(
+            source_dir.join("web").join("node_modules").join("b.js"),
+            vec![0u8; 2048],
+        )
+        .unwrap();
+        fs::write(source_dir.join("src").join("main.rs"), "fn main() {}").unwrap();
+
+        let large_dirs = find_large_dirs(&source_dir, 1024, &["target/".to_string()]);
+        let names: Vec<_> = large_dirs.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["node_modules", "web", "web/node_modules"]);
+
+        // Clean up
+        let _ = fs::remove_dir_all(&source_dir);
+    }
+
+    #[test]
+    fn test_expand_branch_placeholders() {
+        assert_eq!(
+            expand_branch_placeholders(
+                "echo @@branch && git -C @@dir status",
+                "feature/test",
+                Path::new("/repo/.trr/feature-test")
+            ),
+            "echo feature/test && git -C /repo/.trr/feature-test status"
+        );
+    }
+
+    #[test]
+    fn test_detect_git_operation() {
+        let test_id = Ulid::new().to_string();
+        let git_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(&git_dir).unwrap();
+
+        assert_eq!(detect_git_operation(&git_dir), None);
+
+        fs::write(git_dir.join("MERGE_HEAD"), "abc").unwrap();
+        assert_eq!(detect_git_operation(&git_dir), Some("merge"));
+
+        fs::create_dir_all(git_dir.join("rebase-merge")).unwrap();
+        assert_eq!(detect_git_operation(&git_dir), Some("rebase"));
+
+        // Clean up
+        let _ = fs::remove_dir_all(&git_dir);
+    }
+
     #[test]
     fn test_check_tmux_available() {
         // This test just ensures the function runs without panic
@@ -425,10 +3020,181 @@ mod tests {
     }
 
     #[test]
-    fn test_get_repo_prefix() {
-        // This test ensures the function runs and returns a string
-        let prefix = get_repo_prefix();
-        assert!(!prefix.is_empty());
-        assert!(prefix.len() <= 3);
+    fn test_resolve_tmux_mode() {
+        assert_eq!(resolve_tmux_mode(TmuxMode::Auto, true), TmuxMode::Window);
+        assert_eq!(resolve_tmux_mode(TmuxMode::Auto, false), TmuxMode::Session);
+        assert_eq!(
+            resolve_tmux_mode(TmuxMode::Window, false),
+            TmuxMode::Session
+        );
+        assert_eq!(
+            resolve_tmux_mode(TmuxMode::Session, true),
+            TmuxMode::Session
+        );
+        assert_eq!(
+            resolve_tmux_mode(TmuxMode::RepoSession, false),
+            TmuxMode::RepoSession
+        );
+    }
+
+    #[test]
+    fn test_read_in_progress_marker() {
+        let test_id = Ulid::new().to_string();
+        let target_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(&target_dir).unwrap();
+
+        assert_eq!(read_in_progress_marker(&target_dir), None);
+        fs::write(target_dir.join(IN_PROGRESS_MARKER), format!("{test_id}\n")).unwrap();
+        assert_eq!(read_in_progress_marker(&target_dir), Some(test_id));
+
+        // Clean up
+        let _ = fs::remove_dir_all(&target_dir);
+    }
+
+    #[test]
+    fn test_copy_rollback() {
+        let test_dir = std::env::temp_dir().join(format!("trr_test_{}", Ulid::new()));
+        let target_dir = test_dir.join("copy");
+        let metadata_path = test_dir.join("metadata.json");
+        let rollback_for = |keep_interrupted_copy| CopyRollback {
+            metadata_path: metadata_path.clone(),
+            target_dir: target_dir.clone(),
+            worktree: false,
+            worktree_branch: None,
+            keep_interrupted_copy,
+            armed: true,
+        };
+
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(&metadata_path, "{}").unwrap();
+        rollback_for(false).disarm();
+        assert!(target_dir.exists() && metadata_path.exists());
+
+        drop(rollback_for(false));
+        assert!(!target_dir.exists() && !metadata_path.exists());
+
+        // An interrupted copy stays for --resume, but its metadata does not
+        fs::create_dir_all(&target_dir).unwrap();
+        fs::write(target_dir.join(IN_PROGRESS_MARKER), "ulid").unwrap();
+        fs::write(&metadata_path, "{}").unwrap();
+        drop(rollback_for(true));
+        assert!(target_dir.exists() && !metadata_path.exists());
+
+        // Clean up
+        let _ = fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_render_init_commands_alias_override() {
+        let mut config = Config::default();
+        config.settings.tmux_window_init_commands = "nvim".to_string();
+        config.branch_aliases.insert(
+            "@b".to_string(),
+            BranchAlias::Detailed {
+                expansion: "bugfix".to_string(),
+                init_commands: Some("tmux split-window -h".to_string()),
+            },
+        );
+        let options = CreateOptions::default();
+        let dir = Path::new("/tmp/copy");
+
+        let render = |alias| render_init_commands(&config, &options, &[], alias, "bugfix/1", dir);
+        assert_eq!(render(Some("@b")).unwrap(), "tmux split-window -h");
+        assert_eq!(render(Some("@f")).unwrap(), "nvim");
+        assert_eq!(render(None).unwrap(), "nvim");
+    }
+
+    #[test]
+    fn test_metadata_file_name() {
+        let ulid = "01J0000000000000000000000";
+        assert_eq!(
+            metadata_file_name("{ulid}", ulid, "feature-a"),
+            format!("{ulid}.json")
+        );
+        assert_eq!(
+            metadata_file_name("{directory}", ulid, "feature/a"),
+            "feature-a.json"
+        );
+        assert_eq!(
+            metadata_file_name("{directory}-{ulid}", ulid, "feature-a"),
+            format!("feature-a-{ulid}.json")
+        );
+
+        let mut config = Config::default();
+        config.settings.metadata_filename = "copy".to_string();
+        assert!(metadata_file_path(&config, ulid, "feature-a").is_err());
+    }
+
+    #[test]
+    fn test_substitute_args() {
+        let commands = "claude \"@@args!\"\necho @@args?\necho @@args";
+        assert_eq!(
+            substitute_args(commands, "fix it").unwrap(),
+            "claude \"fix it\"\necho fix it\necho fix it"
+        );
+        assert!(substitute_args(commands, "").is_err());
+        assert_eq!(
+            substitute_args("echo @@args?\necho @@args", "").unwrap(),
+            "echo \necho "
+        );
+    }
+
+    #[test]
+    fn test_planned_tmux_kind() {
+        assert_eq!(
+            planned_tmux_kind(TmuxMode::Auto, true, true),
+            TmuxKind::Window
+        );
+        assert_eq!(
+            planned_tmux_kind(TmuxMode::Window, false, true),
+            TmuxKind::Session
+        );
+        assert_eq!(
+            planned_tmux_kind(TmuxMode::RepoSession, false, true),
+            TmuxKind::RepoSession
+        );
+        assert_eq!(
+            planned_tmux_kind(TmuxMode::Session, false, false),
+            TmuxKind::None
+        );
+    }
+
+    #[test]
+    fn test_run_tmux_timeout() {
+        let timeout = Some(Duration::from_millis(100));
+        let error = run_tmux(Command::new("sleep").arg("5"), timeout).unwrap_err();
+        assert_eq!(error.to_string(), "`sleep 5` timed out after 100ms");
+
+        assert!(run_tmux_checked(&mut Command::new("true"), timeout).is_ok());
+        let error = run_tmux_checked(
+            Command::new("sh")
+                .arg("-c")
+                .arg("echo no server >&2; exit 1"),
+            timeout,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`sh -c echo no server >&2; exit 1` failed: no server"
+        );
+    }
+
+    #[test]
+    fn test_parse_tmux_version() {
+        assert_eq!(parse_tmux_version("tmux 3.3a\n"), Some((3, 3)));
+        assert_eq!(parse_tmux_version("tmux 2.4"), Some((2, 4)));
+        assert_eq!(parse_tmux_version("tmux next-3.5"), Some((3, 5)));
+        assert_eq!(parse_tmux_version("tmux master"), None);
+        assert!(parse_tmux_version("tmux 1.8").unwrap() < MIN_SESSION_GROUP_VERSION);
+    }
+
+    #[test]
+    fn test_merge_default_args() {
+        let default_args = vec!["--model".to_string(), "opus".to_string()];
+        assert_eq!(
+            merge_default_args(&default_args, &["fix it".to_string()]),
+            vec!["--model", "opus", "fix it"]
+        );
+        assert_eq!(merge_default_args(&[], &["a".to_string()]), vec!["a"]);
     }
 }
@@ -1,60 +1,72 @@
-use crate::config::Config;
-use crate::create::read_ulid_metadata;
-use chrono::{DateTime, Utc};
+use crate::config::{Config, DeleteConfirm, TmuxMode};
+use crate::create::dir_size;
+use crate::create::{
+    RepositoryMetadata, TmuxKind, git_status_porcelain, read_ulid_metadata, repo_session_target,
+    sanitize_tmux_name, tmux_target_name,
+};
+use crate::git::get_repo_prefix;
+use crate::hooks::{self, HookContext};
+use crate::output::human_size;
 use skim::prelude::*;
 use std::borrow::Cow;
 use std::fs;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, IsTerminal, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
-
-#[derive(Clone)]
-struct Repository {
-    _ulid: String,
-    branch: String,
-    directory: String,
-    path: PathBuf,
-    created_at: DateTime<Utc>,
+use std::thread;
+
+// The ID and directory are resolved for older files that only have them in the file
+// name or derive them from the branch
+#[derive(Clone, Default)]
+pub struct Repository {
+    pub ulid: String,
+    pub directory: String,
+    pub path: PathBuf,
+    pub metadata: RepositoryMetadata,
+    // Walked only by commands that show it, see `load_sizes`
+    pub size: Option<u64>,
 }
 
 impl SkimItem for Repository {
     fn text(&self) -> Cow<str> {
-        let formatted_date = self.created_at.format("%Y-%m-%d %H:%M:%S");
-        Cow::Owned(format!("{}\t{}", formatted_date, self.branch))
+        let formatted_date = self.metadata.created_at.format("%Y-%m-%d %H:%M:%S");
+        if self.metadata.branch_only {
+            Cow::Owned(format!(
+                "{}\t{} (branch only)",
+                formatted_date, self.metadata.branch
+            ))
+        } else if let Some(size) = self.size {
+            Cow::Owned(format!(
+                "{}\t{:>5}\t{}",
+                formatted_date,
+                human_size(size),
+                self.metadata.branch
+            ))
+        } else {
+            Cow::Owned(format!("{}\t{}", formatted_date, self.metadata.branch))
+        }
     }
 }
 
-fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-    let config_path = if let Ok(path) = std::env::var("TRR_CONFIG_PATH") {
-        expand_tilde(&path)
-    } else {
-        dirs::home_dir()
-            .expect("Failed to get home directory")
-            .join(".config")
-            .join("trr")
-            .join("config.toml")
-    };
+// skim tells marked items apart by their index, which is 0 unless an item reports one
+struct IndexedRepository {
+    repo: Repository,
+    index: usize,
+}
 
-    if !config_path.exists() {
-        return Ok(Config::default());
+impl SkimItem for IndexedRepository {
+    fn text(&self) -> Cow<str> {
+        self.repo.text()
     }
 
-    let config_str = fs::read_to_string(&config_path)?;
-    let config: Config = toml::from_str(&config_str)?;
-    Ok(config)
-}
-
-fn expand_tilde(path: &str) -> PathBuf {
-    if path.starts_with('~') {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(&path[2..]);
-        }
+    fn get_index(&self) -> usize {
+        self.index
     }
-    PathBuf::from(path)
 }
 
-fn get_repositories(config: &Config) -> Result<Vec<Repository>, Box<dyn std::error::Error>> {
+pub fn get_repositories(config: &Config) -> Result<Vec<Repository>, Box<dyn std::error::Error>> {
     let trr_sys_path = PathBuf::from(&config.settings.repo_sync_path).join(".trr-sys");
 
     if !trr_sys_path.exists() {
@@ -70,46 +82,77 @@ fn get_repositories(config: &Config) -> Result<Vec<Repository>, Box<dyn std::err
         if path.is_file() {
             let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
-            let ulid = file_name.strip_suffix(".json").unwrap_or(file_name);
+            let file_stem = file_name.strip_suffix(".json").unwrap_or(file_name);
 
             if let Ok(metadata) = read_ulid_metadata(&path) {
+                let ulid = metadata
+                    .ulid
+                    .clone()
+                    .unwrap_or_else(|| file_stem.to_string());
                 let directory = metadata
                     .directory
+                    .clone()
                     .unwrap_or_else(|| crate::create::branch_to_directory_name(&metadata.branch));
                 repositories.push(Repository {
-                    _ulid: ulid.to_string(),
-                    branch: metadata.branch.clone(),
+                    ulid,
                     directory,
                     path,
-                    created_at: metadata.created_at,
+                    metadata,
+                    size: None,
                 });
             }
         }
     }
 
-    repositories.sort_by(|a, b| a.branch.cmp(&b.branch));
+    repositories.sort_by(|a, b| a.metadata.branch.cmp(&b.metadata.branch));
     Ok(repositories)
 }
 
-fn select_repository_with_skim(
+// Each copy is walked once, in parallel; branch-only copies have nothing on disk.
+// Unreadable files and directories count as 0 instead of failing the whole listing
+pub fn load_sizes(repo_sync_path: &Path, repositories: &mut [Repository]) {
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = repositories.len().div_ceil(workers).max(1);
+
+    thread::scope(|scope| {
+        for chunk in repositories.chunks_mut(chunk_size) {
+            scope.spawn(move || {
+                for repo in chunk.iter_mut().filter(|repo| !repo.metadata.branch_only) {
+                    repo.size = Some(dir_size(&repo_sync_path.join(&repo.directory)));
+                }
+            });
+        }
+    });
+}
+
+// With `multi`, Tab marks several repositories; indices come back in list order
+pub fn select_repository_with_skim(
     repositories: &[Repository],
-) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    multi: bool,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
     if repositories.is_empty() {
         println!("No repositories found.");
-        return Ok(None);
+        return Ok(Vec::new());
     }
 
     let options = SkimOptionsBuilder::default()
         .height("50%".to_string())
         .prompt("Select repository> ".to_string())
         .layout("reverse".to_string())
+        .multi(multi)
         .build()
         .unwrap();
 
     let items: Vec<(usize, Arc<dyn SkimItem>)> = repositories
         .iter()
         .enumerate()
-        .map(|(idx, repo)| (idx, Arc::new(repo.clone()) as Arc<dyn SkimItem>))
+        .map(|(idx, repo)| {
+            let item = IndexedRepository {
+                repo: repo.clone(),
+                index: idx,
+            };
+            (idx, Arc::new(item) as Arc<dyn SkimItem>)
+        })
         .collect();
 
     let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
@@ -119,113 +162,277 @@ fn select_repository_with_skim(
     }
     drop(tx);
 
-    let selected = Skim::run_with(&options, Some(rx));
+    let Some(output) = Skim::run_with(&options, Some(rx)) else {
+        return Ok(Vec::new());
+    };
+    if output.is_abort {
+        return Ok(Vec::new());
+    }
 
-    if let Some(output) = selected {
-        if output.is_abort {
-            return Ok(None);
-        }
+    let mut selected: Vec<usize> = output
+        .selected_items
+        .iter()
+        .filter_map(|selected_item| {
+            items
+                .iter()
+                .find(|(_, item)| Arc::ptr_eq(item, selected_item))
+                .map(|(original_idx, _)| *original_idx)
+        })
+        .collect();
+    selected.sort_unstable();
+    Ok(selected)
+}
 
-        if let Some(selected_item) = output.selected_items.first() {
-            for (original_idx, item) in &items {
-                if Arc::ptr_eq(item, selected_item) {
-                    return Ok(Some(*original_idx));
-                }
-            }
-        }
+pub fn find_repository(
+    repositories: &[Repository],
+    branch: Option<&str>,
+) -> Result<Option<Repository>, Box<dyn std::error::Error>> {
+    match branch {
+        Some(branch) => repositories
+            .iter()
+            .find(|repo| repo.metadata.branch == branch)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| {
+                let available: Vec<&str> = repositories
+                    .iter()
+                    .map(|repo| repo.metadata.branch.as_str())
+                    .collect();
+                format!(
+                    "No repository found for branch '{branch}'. Available branches: {}",
+                    if available.is_empty() {
+                        "(none)".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )
+                .into()
+            }),
+        None => Ok(select_repository_with_skim(repositories, false)?
+            .first()
+            .map(|&i| repositories[i].clone())),
     }
+}
 
-    Ok(None)
+// Runs a tmux listing command, giving None when there is no server or the target is missing
+fn tmux_listing(args: &[&str]) -> Option<String> {
+    let output = Command::new("tmux").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
-fn find_tmux_session_or_window(branch: &str) -> Option<(String, bool)> {
-    let repo_prefix = get_repo_prefix();
-    let name = format!("{repo_prefix}-{branch}");
+type TmuxLister<'a> = &'a dyn Fn(&[&str]) -> Option<String>;
 
-    let in_tmux = std::env::var("TMUX").is_ok();
+fn find_repo_session_window(repo_prefix: &str, branch: &str, list: TmuxLister) -> Option<String> {
+    let session = format!("={repo_prefix}");
+    let windows = list(&["list-windows", "-t", &session, "-F", "#{window_name}"])?;
+    if contains_name(&windows, &sanitize_tmux_name(branch)) {
+        return Some(repo_session_target(repo_prefix, branch));
+    }
+    // Windows created before names were sanitized keep the raw branch name
+    contains_name(&windows, branch).then(|| format!("{repo_prefix}:{branch}"))
+}
 
-    if in_tmux {
-        let output = Command::new("tmux")
-            .arg("list-windows")
-            .arg("-F")
-            .arg("#{window_name}")
-            .output()
-            .ok()?;
+// Search every session, since `create --window`/`--session` can override tmux_mode
+// and the window may live in a session other than the current one
+fn find_window(names: &[&str], list: TmuxLister) -> Option<String> {
+    let windows = list(&["list-windows", "-a", "-F", "#{session_name}:#{window_name}"])?;
+    names
+        .iter()
+        .find_map(|name| find_window_target(&windows, name))
+}
 
-        let windows = String::from_utf8_lossy(&output.stdout);
-        for window in windows.lines() {
-            if window == name {
-                return Some((name, true));
-            }
+fn find_session(names: &[&str], list: TmuxLister) -> Option<String> {
+    let sessions = list(&["list-sessions", "-F", "#{session_name}"])?;
+    names
+        .iter()
+        .find(|name| contains_name(&sessions, name))
+        .map(|name| name.to_string())
+}
+
+// The kind recorded at creation is looked for first. The rest are probed in case
+// the copy was created before it was recorded or reopened differently since
+fn tmux_lookup_order(config: &Config, recorded: Option<TmuxKind>) -> Vec<TmuxKind> {
+    let mut order: Vec<TmuxKind> = recorded.into_iter().collect();
+    let repo_session =
+        (config.settings.tmux_mode == TmuxMode::RepoSession).then_some(TmuxKind::RepoSession);
+    for kind in repo_session
+        .into_iter()
+        .chain([TmuxKind::Window, TmuxKind::Session])
+    {
+        if !order.contains(&kind) {
+            order.push(kind);
         }
     }
+    order
+}
 
-    let output = Command::new("tmux")
-        .arg("list-sessions")
-        .arg("-F")
-        .arg("#{session_name}")
-        .output()
-        .ok()?;
+pub fn find_tmux_session_or_window(
+    config: &Config,
+    branch: &str,
+    recorded: Option<TmuxKind>,
+) -> Option<(String, bool)> {
+    locate_tmux_target(config, branch, recorded, &tmux_listing)
+}
 
-    let sessions = String::from_utf8_lossy(&output.stdout);
-    for session in sessions.lines() {
-        if session == name {
-            return Some((name, false));
+fn locate_tmux_target(
+    config: &Config,
+    branch: &str,
+    recorded: Option<TmuxKind>,
+    list: TmuxLister,
+) -> Option<(String, bool)> {
+    let repo_prefix = get_repo_prefix(config);
+    let name = tmux_target_name(&repo_prefix, &config.settings.name_separator, branch);
+    let legacy_name = format!("{repo_prefix}{}{branch}", config.settings.name_separator);
+    let names = [name.as_str(), legacy_name.as_str()];
+
+    tmux_lookup_order(config, recorded)
+        .into_iter()
+        .find_map(|kind| match kind {
+            TmuxKind::RepoSession => {
+                find_repo_session_window(&repo_prefix, branch, list).map(|target| (target, true))
+            }
+            TmuxKind::Window => find_window(&names, list).map(|target| (target, true)),
+            TmuxKind::Session => find_session(&names, list).map(|name| (name, false)),
+            TmuxKind::None => None,
+        })
+}
+
+fn contains_name(listing: &str, name: &str) -> bool {
+    listing.lines().any(|line| line == name)
+}
+
+// tmux replaces ':' in session names, so the first ':' separates the window name
+fn find_window_target(listing: &str, name: &str) -> Option<String> {
+    listing
+        .lines()
+        .find(|line| {
+            line.split_once(':')
+                .is_some_and(|(_, window)| window == name)
+        })
+        .map(str::to_string)
+}
+
+fn print_git_summary(dir: &Path, dirty_ignore: &[String]) {
+    if let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("log")
+        .arg("--oneline")
+        .arg("-5")
+        .output()
+    {
+        if output.status.success() {
+            println!("Recent commits:");
+            print!("{}", String::from_utf8_lossy(&output.stdout));
+            println!();
         }
     }
 
-    None
+    if let Some(status) = git_status_porcelain(dir, dirty_ignore) {
+        if status.is_empty() {
+            println!("Working tree clean.");
+        } else {
+            println!("Uncommitted changes:");
+            print!("{status}");
+        }
+        println!();
+    }
 }
 
-fn get_repo_prefix() -> String {
-    if let Some(repo_name) = get_repo_name() {
-        repo_name.chars().take(3).collect()
-    } else {
-        std::env::current_dir()
-            .ok()
-            .and_then(|dir| {
-                dir.file_name()
-                    .map(|name| name.to_string_lossy().to_string())
-            })
-            .map(|name| name.chars().take(3).collect())
-            .unwrap_or_else(|| "trr".to_string())
-    }
-}
-
-fn get_repo_name() -> Option<String> {
-    let output = Command::new("git")
-        .arg("remote")
-        .arg("get-url")
-        .arg("origin")
-        .output()
-        .ok()?;
+fn delete_git_branch(source_dir: &Path, branch: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let delete_result = Command::new("git")
+        .arg("-C")
+        .arg(source_dir)
+        .arg("branch")
+        .arg("-D")
+        .arg(branch)
+        .output()?;
+
+    if !delete_result.status.success() {
+        eprintln!(
+            "Failed to delete git branch. stderr: {}",
+            String::from_utf8_lossy(&delete_result.stderr)
+        );
+        return Err("Failed to delete git branch".into());
+    }
+    Ok(())
+}
 
-    if !output.status.success() {
-        return None;
+fn remove_git_worktree(source_dir: &Path, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    // --force because deletion was already confirmed, even with local changes
+    let remove_result = Command::new("git")
+        .arg("-C")
+        .arg(source_dir)
+        .arg("worktree")
+        .arg("remove")
+        .arg("--force")
+        .arg(dir)
+        .output()?;
+
+    if !remove_result.status.success() {
+        eprintln!(
+            "Failed to remove git worktree. stderr: {}",
+            String::from_utf8_lossy(&remove_result.stderr)
+        );
+        return Err("Failed to remove git worktree".into());
     }
+    Ok(())
+}
+
+// `git worktree remove` leaves the branch behind in the source repository. The copy
+// is already gone when deleting it fails, so that only warns
+fn remove_worktree_copy(
+    source_dir: &Path,
+    dir: &Path,
+    branch: &str,
+    delete_branch: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Removing worktree: {}", dir.display());
+    remove_git_worktree(source_dir, dir)?;
 
-    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    let repo_name = if url.starts_with("https://") || url.starts_with("http://") {
-        url.split('/')
-            .next_back()?
-            .trim_end_matches(".git")
-            .to_string()
-    } else if url.contains(':') {
-        url.split(':')
-            .next_back()?
-            .split('/')
-            .next_back()?
-            .trim_end_matches(".git")
-            .to_string()
+    if delete_branch {
+        println!("Deleting branch: {branch}");
+        if delete_git_branch(source_dir, branch).is_err() {
+            eprintln!("Warning: branch '{branch}' was left in the source repository");
+        }
     } else {
-        return None;
-    };
+        println!("Keeping branch: {branch}");
+    }
+    Ok(())
+}
+
+// Copies created before the source path was recorded were made from the current directory
+fn source_dir(repo: &Repository) -> PathBuf {
+    repo.metadata
+        .source_path
+        .as_ref()
+        .map_or_else(|| PathBuf::from("."), PathBuf::from)
+}
 
-    Some(repo_name)
+// Branch-only and worktree copies share their branch with the source repository, so it
+// is only deleted on request and after confirmation
+fn confirm_branch_deletion(
+    config: &Config,
+    repo: &Repository,
+    delete_branch: bool,
+    keep_branch: bool,
+    yes: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let requested = !keep_branch && (delete_branch || config.settings.delete_branch);
+    if !requested || !(repo.metadata.branch_only || repo.metadata.worktree) {
+        return Ok(false);
+    }
+    Ok(yes
+        || confirm(&format!(
+            "Also delete git branch '{}'?",
+            repo.metadata.branch
+        ))?)
 }
 
-fn kill_tmux_session_or_window(
+pub fn kill_tmux_session_or_window(
     name: &str,
     is_window: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -245,54 +452,510 @@ fn kill_tmux_session_or_window(
     Ok(())
 }
 
-pub fn delete_repo() -> Result<(), Box<dyn std::error::Error>> {
-    let config = load_config()?;
-    let repositories = get_repositories(&config)?;
+fn confirm(prompt: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    print!("{prompt} [y/N]: ");
+    io::stdout().flush()?;
 
-    if let Some(index) = select_repository_with_skim(&repositories)? {
-        let repo = &repositories[index];
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-        Command::new("clear").status().ok();
+    Ok(input.trim().to_lowercase() == "y")
+}
 
-        println!("Selected repository: {}", repo.branch);
-        println!(
-            "Created at: {}",
-            repo.created_at.format("%Y-%m-%d %H:%M:%S")
-        );
-        println!();
+// Copies created with --no-sanitize-dir live in nested directories
+pub fn remove_empty_parents(dir: &Path, root: &Path) {
+    let mut parent = dir.parent();
+    while let Some(dir) = parent.filter(|dir| *dir != root && dir.starts_with(root)) {
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+}
 
-        print!("Are you sure you want to delete this repository? [y/N]: ");
-        io::stdout().flush()?;
+fn confirm_typed(subject: &str, expected: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    print!("Type {subject} '{expected}' to confirm deletion: ");
+    io::stdout().flush()?;
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
 
-        if input.trim().to_lowercase() != "y" {
-            println!("Deletion cancelled.");
-            return Ok(());
+    Ok(input.trim() == expected)
+}
+
+pub fn remove_repository(
+    config: &Config,
+    repo: &Repository,
+    delete_branch: bool,
+    keep_branch: bool,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let repo_dir = PathBuf::from(&config.settings.repo_sync_path).join(&repo.directory);
+    let absolute_repo_dir = std::env::current_dir()?.join(&repo_dir);
+    let hook_context = HookContext {
+        branch: &repo.metadata.branch,
+        dir: &absolute_repo_dir,
+        ulid: &repo.ulid,
+    };
+    hooks::run_hooks(config, "pre-delete", &hook_context)?;
+
+    let source_dir = source_dir(repo);
+    let delete_shared_branch =
+        confirm_branch_deletion(config, repo, delete_branch, keep_branch, yes)?;
+
+    if repo.metadata.branch_only {
+        if delete_shared_branch {
+            println!("Deleting branch: {}", repo.metadata.branch);
+            delete_git_branch(&source_dir, &repo.metadata.branch)?;
+        } else {
+            println!("Keeping branch: {}", repo.metadata.branch);
         }
+        fs::remove_file(&repo.path)?;
+        hooks::run_hooks(config, "post-delete", &hook_context)?;
+        hooks::run_post_delete_command(config, &hook_context);
 
-        if let Some((tmux_name, is_window)) = find_tmux_session_or_window(&repo.branch) {
-            println!(
-                "Killing tmux {}: {}",
-                if is_window { "window" } else { "session" },
-                tmux_name
-            );
-            kill_tmux_session_or_window(&tmux_name, is_window)?;
+        println!("Successfully deleted repository '{}'", repo.metadata.branch);
+        return Ok(());
+    }
+
+    if keep_branch && !repo.metadata.worktree {
+        println!(
+            "Note: --keep-branch has no effect on rsync copies; their branch lives only in the copy and is removed with it"
+        );
+    }
+
+    if repo.metadata.session_group.is_some() {
+        let session_name = tmux_target_name(
+            &get_repo_prefix(config),
+            &config.settings.name_separator,
+            &repo.metadata.branch,
+        );
+        // The copy's window is linked into every session of the group, so it is
+        // destroyed before the copy's own session
+        println!("Killing tmux session: {session_name}");
+        kill_tmux_session_or_window(&format!("{session_name}:{session_name}"), true)?;
+        kill_tmux_session_or_window(&session_name, false)?;
+    } else if let Some((tmux_name, is_window)) =
+        find_tmux_session_or_window(config, &repo.metadata.branch, repo.metadata.tmux_kind)
+    {
+        println!(
+            "Killing tmux {}: {}",
+            if is_window { "window" } else { "session" },
+            tmux_name
+        );
+        kill_tmux_session_or_window(&tmux_name, is_window)?;
+    }
+
+    if repo_dir.exists() {
+        // Unlink shared paths first so removal never touches the source files
+        for linked_path in &repo.metadata.linked_paths {
+            let link = repo_dir.join(linked_path);
+            if link.is_symlink() {
+                fs::remove_file(&link)?;
+            }
         }
 
-        let repo_dir = PathBuf::from(&config.settings.repo_sync_path).join(&repo.directory);
-        if repo_dir.exists() {
+        if repo.metadata.worktree {
+            remove_worktree_copy(
+                &source_dir,
+                &absolute_repo_dir,
+                &repo.metadata.branch,
+                delete_shared_branch,
+            )?;
+        } else {
             println!("Removing directory: {}", repo_dir.display());
             fs::remove_dir_all(&repo_dir)?;
         }
+        remove_empty_parents(&repo_dir, Path::new(&config.settings.repo_sync_path));
+    }
 
-        fs::remove_file(&repo.path)?;
+    fs::remove_file(&repo.path)?;
+    hooks::run_hooks(config, "post-delete", &hook_context)?;
+    hooks::run_post_delete_command(config, &hook_context);
 
-        println!("Successfully deleted repository '{}'", repo.branch);
-    } else {
+    println!("Successfully deleted repository '{}'", repo.metadata.branch);
+    Ok(())
+}
+
+fn print_selected_repository(config: &Config, repo: &Repository) {
+    println!("Selected repository: {}", repo.metadata.branch);
+    println!(
+        "Created at: {}",
+        repo.metadata.created_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(source_commit) = &repo.metadata.source_commit {
+        println!("Source commit: {source_commit}");
+    }
+    println!();
+
+    let repo_dir = PathBuf::from(&config.settings.repo_sync_path).join(&repo.directory);
+    if config.settings.show_log_on_delete && !repo.metadata.branch_only && repo_dir.exists() {
+        print_git_summary(&repo_dir, &config.settings.dirty_ignore);
+    }
+}
+
+fn deletion_summary(total: usize, failures: &[(String, String)]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Deleted {} of {total} repositories",
+        total - failures.len()
+    )];
+    for (branch, error) in failures {
+        lines.push(format!("  Failed: {branch}: {error}"));
+    }
+    lines
+}
+
+pub fn delete_repo(
+    branch: Option<&str>,
+    delete_branch: bool,
+    keep_branch: bool,
+    confirm_branch: bool,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = crate::config::load()?;
+    let mut repositories = get_repositories(&config)?;
+    // Shown in the picker so the largest copies stand out
+    if branch.is_none() {
+        load_sizes(
+            Path::new(&config.settings.repo_sync_path),
+            &mut repositories,
+        );
+    }
+
+    let selected: Vec<Repository> = match branch {
+        Some(branch) => find_repository(&repositories, Some(branch))?
+            .into_iter()
+            .collect(),
+        None => select_repository_with_skim(&repositories, true)?
+            .into_iter()
+            .map(|i| repositories[i].clone())
+            .collect(),
+    };
+    if selected.is_empty() {
         println!("No repository selected.");
+        return Ok(());
+    }
+
+    if config.settings.clear_before_confirm && io::stdout().is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
+    for repo in &selected {
+        print_selected_repository(&config, repo);
+    }
+
+    // Reading the answer from a pipe would block or consume unrelated input
+    if !yes && !io::stdin().is_terminal() {
+        return Err("stdin is not a terminal. Pass --yes to delete without confirmation.".into());
+    }
+
+    let typed = confirm_branch || config.settings.delete_confirm == DeleteConfirm::Typed;
+    let confirmed = match (yes, typed, selected.as_slice()) {
+        (true, _, _) => true,
+        (false, true, [repo]) => confirm_typed("the branch name", &repo.metadata.branch)?,
+        (false, true, _) => {
+            confirm_typed("the number of repositories", &selected.len().to_string())?
+        }
+        (false, false, [_]) => confirm("Are you sure you want to delete this repository?")?,
+        (false, false, _) => confirm(&format!(
+            "Are you sure you want to delete these {} repositories?",
+            selected.len()
+        ))?,
+    };
+    if !confirmed {
+        println!("Deletion cancelled.");
+        return Ok(());
+    }
+
+    if let [repo] = selected.as_slice() {
+        return remove_repository(&config, repo, delete_branch, keep_branch, yes);
     }
 
+    // One failing copy shouldn't keep the rest around
+    let mut failures = Vec::new();
+    for repo in &selected {
+        if let Err(e) = remove_repository(&config, repo, delete_branch, keep_branch, yes) {
+            eprintln!("Failed to delete '{}': {e}", repo.metadata.branch);
+            failures.push((repo.metadata.branch.clone(), e.to_string()));
+        }
+    }
+
+    println!();
+    for line in deletion_summary(selected.len(), &failures) {
+        println!("{line}");
+    }
+    if !failures.is_empty() {
+        return Err(format!("Failed to delete {} repositories", failures.len()).into());
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IdScheme;
+
+    fn repository(branch: &str) -> Repository {
+        Repository {
+            directory: branch.replace('/', "-"),
+            metadata: RepositoryMetadata {
+                branch: branch.to_string(),
+                source_path: Some("/repo".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deletion_summary() {
+        assert_eq!(
+            deletion_summary(3, &[]),
+            vec!["Deleted 3 of 3 repositories"]
+        );
+        assert_eq!(
+            deletion_summary(3, &[("feature/b".to_string(), "busy".to_string())]),
+            vec!["Deleted 2 of 3 repositories", "  Failed: feature/b: busy"]
+        );
+    }
+
+    #[test]
+    fn test_load_sizes() {
+        let test_id = ulid::Ulid::new().to_string();
+        let base_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        let mut repositories: Vec<_> = (0..10)
+            .map(|i| repository(&format!("feature/{i}")))
+            .collect();
+        for (i, repo) in repositories.iter().enumerate() {
+            let dir = base_dir.join(&repo.directory).join("src");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("file"), vec![0u8; i * 10]).unwrap();
+        }
+        repositories[3].metadata.branch_only = true;
+        repositories.push(repository("feature/missing"));
+
+        load_sizes(&base_dir, &mut repositories);
+        let sizes: Vec<_> = repositories.iter().map(|repo| repo.size).collect();
+        let mut expected: Vec<_> = (0..10).map(|i| Some(i * 10)).collect();
+        expected[3] = None;
+        expected.push(Some(0));
+        assert_eq!(sizes, expected);
+        assert!(repositories[1].text().contains("10B"));
+
+        // Clean up
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_find_repository_by_branch() {
+        let repositories = vec![repository("feature/a"), repository("feature/b")];
+
+        let repo = find_repository(&repositories, Some("feature/b"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(repo.directory, "feature-b");
+        let Err(error) = find_repository(&repositories, Some("feature/c")) else {
+            panic!("expected an error for an unknown branch");
+        };
+        assert!(
+            error
+                .to_string()
+                .ends_with("Available branches: feature/a, feature/b")
+        );
+    }
+
+    #[test]
+    fn test_remove_nested_copy_directory() {
+        let test_id = ulid::Ulid::new().to_string();
+        let root = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        let repo_dir = root.join("feature/nested/api");
+        fs::create_dir_all(&repo_dir).unwrap();
+        fs::create_dir_all(root.join("feature/other")).unwrap();
+
+        fs::remove_dir_all(&repo_dir).unwrap();
+        remove_empty_parents(&repo_dir, &root);
+
+        assert!(!root.join("feature/nested").exists());
+        assert!(root.join("feature/other").exists());
+        assert!(root.exists());
+
+        // Clean up
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_remove_worktree_copy() {
+        let test_id = ulid::Ulid::new().to_string();
+        let source_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(&source_dir).unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&source_dir)
+                .args(["-c", "user.name=trr", "-c", "user.email=trr@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+        let branch_exists = |branch: &str| {
+            git(&[
+                "rev-parse",
+                "--verify",
+                "-q",
+                &format!("refs/heads/{branch}"),
+            ])
+            .status
+            .success()
+        };
+
+        for (branch, delete_branch) in [("feature/drop", true), ("feature/keep", false)] {
+            let dir = source_dir.join(".trr").join(branch.replace('/', "-"));
+            let dir_arg = dir.to_string_lossy();
+            assert!(
+                git(&["worktree", "add", "-q", "-b", branch, &dir_arg])
+                    .status
+                    .success()
+            );
+
+            remove_worktree_copy(&source_dir, &dir, branch, delete_branch).unwrap();
+            assert!(!dir.exists());
+            assert_eq!(branch_exists(branch), !delete_branch);
+        }
+
+        // Clean up
+        let _ = fs::remove_dir_all(&source_dir);
+    }
+
+    #[test]
+    fn test_tmux_lookup_order() {
+        let mut config = Config::default();
+        assert_eq!(
+            tmux_lookup_order(&config, None),
+            vec![TmuxKind::Window, TmuxKind::Session]
+        );
+        assert_eq!(
+            tmux_lookup_order(&config, Some(TmuxKind::Session)),
+            vec![TmuxKind::Session, TmuxKind::Window]
+        );
+
+        config.settings.tmux_mode = TmuxMode::RepoSession;
+        assert_eq!(
+            tmux_lookup_order(&config, Some(TmuxKind::Window)),
+            vec![TmuxKind::Window, TmuxKind::RepoSession, TmuxKind::Session]
+        );
+    }
+
+    #[test]
+    fn test_custom_separator_round_trip() {
+        if Command::new("tmux").arg("-V").output().is_err() {
+            eprintln!("Skipping: tmux is not installed");
+            return;
+        }
+
+        // A private server keeps the test away from the user's sessions
+        let socket = format!("trr_test_{}", ulid::Ulid::new());
+        let tmux = |args: &[&str]| {
+            Command::new("tmux")
+                .args(["-L", &socket, "-f", "/dev/null"])
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        let list = |args: &[&str]| tmux_listing(&[&["-L", socket.as_str()], args].concat());
+
+        let mut config = Config::default();
+        config.settings.repo_prefix = Some("trr".to_string());
+        config.settings.name_separator = "_".to_string();
+        let session = tmux_target_name("trr", "_", "feature/my-branch");
+        let window = tmux_target_name("trr", "_", "fix/other");
+        assert!(
+            tmux(&["new-session", "-d", "-s", &session])
+                .status
+                .success()
+        );
+        assert!(
+            tmux(&[
+                "new-window",
+                "-d",
+                "-t",
+                &format!("={session}"),
+                "-n",
+                &window
+            ])
+            .status
+            .success()
+        );
+
+        assert_eq!(
+            locate_tmux_target(&config, "feature/my-branch", Some(TmuxKind::Session), &list),
+            Some((session.clone(), false))
+        );
+        assert_eq!(
+            locate_tmux_target(&config, "fix/other", Some(TmuxKind::Window), &list),
+            Some((format!("{session}:{window}"), true))
+        );
+        // A wrong recorded kind still falls through to the other lookups
+        assert_eq!(
+            locate_tmux_target(&config, "feature/my-branch", Some(TmuxKind::Window), &list),
+            Some((session.clone(), false))
+        );
+        assert_eq!(
+            locate_tmux_target(&config, "feature/missing", None, &list),
+            None
+        );
+
+        // Clean up
+        tmux(&["kill-server"]);
+    }
+
+    #[test]
+    fn test_get_repositories_reads_stored_ulid() {
+        let test_id = ulid::Ulid::new().to_string();
+        let base_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        let mut config = Config::default();
+        config.settings.repo_sync_path = base_dir.to_string_lossy().to_string();
+        config.settings.metadata_filename = "{directory}".to_string();
+
+        // Any id scheme is read back as stored
+        let id = crate::create::generate_id(IdScheme::Uuid);
+        let metadata = crate::create::RepositoryMetadata {
+            ulid: Some(id.clone()),
+            id_scheme: Some(IdScheme::Uuid),
+            branch: "feature/test".to_string(),
+            directory: Some("feature-test".to_string()),
+            tmux_kind: Some(TmuxKind::RepoSession),
+            ..Default::default()
+        };
+        let path = crate::create::write_metadata(&config, &id, &metadata).unwrap();
+        assert!(path.ends_with(".trr-sys/feature-test.json"));
+        assert!(
+            fs::read_to_string(&path)
+                .unwrap()
+                .contains(r#""tmux_kind": "repo-session""#)
+        );
+
+        let repositories = get_repositories(&config).unwrap();
+        assert_eq!(repositories.len(), 1);
+        assert_eq!(repositories[0].ulid, id);
+        assert_eq!(repositories[0].metadata.id_scheme, Some(IdScheme::Uuid));
+        assert_eq!(repositories[0].path, path);
+        assert_eq!(
+            repositories[0].metadata.tmux_kind,
+            Some(TmuxKind::RepoSession)
+        );
+
+        // Clean up
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_find_window_target() {
+        let listing = "main:nvim\nwork:trr-feature-a\nwork:trr-feature-ab\n";
+        assert_eq!(
+            find_window_target(listing, "trr-feature-a"),
+            Some("work:trr-feature-a".to_string())
+        );
+        assert_eq!(find_window_target(listing, "feature-a"), None);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::config::{self, Config};
+use crate::delete::get_repositories;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Modified(String),
+    Added(String),
+    Deleted(String),
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Modified(path) => write!(f, "M\t{path}"),
+            Change::Added(path) => write!(f, "A\t{path}"),
+            Change::Deleted(path) => write!(f, "D\t{path}"),
+        }
+    }
+}
+
+fn relative_path(dir: &str, base: &str, name: &str) -> String {
+    let dir = dir
+        .strip_prefix(base)
+        .unwrap_or(dir)
+        .trim_start_matches('/');
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+fn parse_diff_output(output: &str, source: &str, copy: &str) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Only in ") {
+            let Some((dir, name)) = rest.split_once(": ") else {
+                continue;
+            };
+            // The copy lives inside the source, so check the longer prefix first
+            if dir.starts_with(copy) {
+                changes.push(Change::Added(relative_path(dir, copy, name)));
+            } else {
+                changes.push(Change::Deleted(relative_path(dir, source, name)));
+            }
+        } else if let Some(rest) = line.strip_prefix("Files ") {
+            let Some((source_file, _)) = rest.split_once(" and ") else {
+                continue;
+            };
+            let path = source_file
+                .strip_prefix(source)
+                .unwrap_or(source_file)
+                .trim_start_matches('/');
+            changes.push(Change::Modified(path.to_string()));
+        }
+    }
+
+    changes
+}
+
+fn diff_against_source(
+    config: &Config,
+    source_dir: &Path,
+    copy_dir: &Path,
+) -> Result<Vec<Change>, Box<dyn std::error::Error>> {
+    let mut diff_command = Command::new("diff");
+    diff_command.arg("-rq").arg("-x").arg(".git");
+
+    // diff -x matches file names, so only the last component of repo_sync_path applies
+    if let Some(sync_dir_name) = Path::new(&config.settings.repo_sync_path).file_name() {
+        diff_command.arg("-x").arg(sync_dir_name);
+    }
+    for exclude in &config.settings.rsync_excludes {
+        diff_command.arg("-x").arg(exclude.trim_end_matches('/'));
+    }
+
+    let source = source_dir.to_string_lossy().to_string();
+    let copy = copy_dir.to_string_lossy().to_string();
+    let output = diff_command.arg(&source).arg(&copy).output()?;
+
+    // diff exits with 1 when the trees differ and 2 on trouble
+    if output.status.code() == Some(2) {
+        eprintln!(
+            "Failed to diff repository. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err("diff failed".into());
+    }
+
+    Ok(parse_diff_output(
+        &String::from_utf8_lossy(&output.stdout),
+        &source,
+        &copy,
+    ))
+}
+
+fn diff_against_commit(copy_dir: &Path, commit: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .arg("diff")
+        .arg("--name-status")
+        .arg(commit)
+        .current_dir(copy_dir)
+        .output()?;
+
+    if !output.status.success() {
+        eprintln!(
+            "Failed to run git diff. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err("git diff failed".into());
+    }
+
+    let changes = String::from_utf8_lossy(&output.stdout);
+    if changes.trim().is_empty() {
+        println!("No changes since {commit}.");
+    } else {
+        print!("{changes}");
+    }
+    Ok(())
+}
+
+pub fn diff_repo(branch: &str, against_commit: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+
+    let repo = repositories
+        .iter()
+        .find(|repo| repo.metadata.branch == branch)
+        .ok_or_else(|| format!("No repository found for branch '{branch}'"))?;
+
+    if repo.metadata.branch_only {
+        return Err(format!("'{branch}' was created with --branch-only and has no copy").into());
+    }
+
+    let source_dir = std::env::current_dir()?;
+    let copy_dir = source_dir
+        .join(PathBuf::from(&config.settings.repo_sync_path))
+        .join(&repo.directory);
+    if !copy_dir.exists() {
+        return Err(format!("Directory '{}' does not exist", copy_dir.display()).into());
+    }
+
+    if against_commit {
+        let commit = repo
+            .metadata
+            .source_commit
+            .as_deref()
+            .ok_or_else(|| format!("No source commit recorded for branch '{branch}'"))?;
+        return diff_against_commit(&copy_dir, commit);
+    }
+
+    let changes = diff_against_source(&config, &source_dir, &copy_dir)?;
+    if changes.is_empty() {
+        println!("No differences from the source.");
+    } else {
+        for change in &changes {
+            println!("{change}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diff_output() {
+        let output = "\
+Files /repo/src/main.rs and /repo/.trr/feature-x/src/main.rs differ
+Only in /repo/.trr/feature-x/src: new.rs
+Only in /repo/.trr/feature-x: NOTES.md
+Only in /repo/docs: removed.md
+";
+        let changes = parse_diff_output(output, "/repo", "/repo/.trr/feature-x");
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Modified("src/main.rs".to_string()),
+                Change::Added("src/new.rs".to_string()),
+                Change::Added("NOTES.md".to_string()),
+                Change::Deleted("docs/removed.md".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_display() {
+        assert_eq!(Change::Modified("a.rs".to_string()).to_string(), "M\ta.rs");
+        assert_eq!(Change::Added("b.rs".to_string()).to_string(), "A\tb.rs");
+        assert_eq!(Change::Deleted("c.rs".to_string()).to_string(), "D\tc.rs");
+    }
+}
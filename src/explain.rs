@@ -0,0 +1,75 @@
+use crate::config::{self, Config};
+use crate::create::{AliasExpansion, branch_to_directory_name, resolve_alias, tmux_target_name};
+use crate::git::get_repo_prefix;
+use crate::output;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+struct Explanation {
+    input: String,
+    #[serde(flatten)]
+    expansion: AliasExpansion,
+    directory: String,
+    tmux_name: String,
+}
+
+fn explain(branch: &str, config: &Config, repo_prefix: &str) -> Explanation {
+    let expansion = resolve_alias(branch, config);
+    let directory = PathBuf::from(&config.settings.repo_sync_path)
+        .join(branch_to_directory_name(&expansion.branch))
+        .to_string_lossy()
+        .to_string();
+    let tmux_name = tmux_target_name(
+        repo_prefix,
+        &config.settings.name_separator,
+        &expansion.branch,
+    );
+
+    Explanation {
+        input: branch.to_string(),
+        expansion,
+        directory,
+        tmux_name,
+    }
+}
+
+pub fn explain_alias(branch: &str, json_pretty: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let explanation = explain(branch, &config, &get_repo_prefix(&config));
+    println!("{}", output::to_json(&explanation, json_pretty)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_dynamic_alias() {
+        let mut config = Config::default();
+        config.branch_aliases.clear();
+        config
+            .branch_aliases
+            .insert("@t".to_string(), "!echo feature/20250708".into());
+
+        let explanation = serde_json::to_value(explain("@t-api", &config, "trr")).unwrap();
+
+        assert_eq!(explanation["input"], "@t-api");
+        assert_eq!(explanation["alias"], "@t");
+        assert_eq!(explanation["is_command"], true);
+        assert_eq!(explanation["expansion"], "feature/20250708");
+        assert_eq!(explanation["branch"], "feature/20250708-api");
+        assert_eq!(explanation["directory"], ".trr/feature-20250708-api");
+        assert_eq!(explanation["tmux_name"], "trr-feature-20250708-api");
+    }
+
+    #[test]
+    fn test_explain_without_alias() {
+        let config = Config::default();
+        let explanation = serde_json::to_value(explain("fix/typo", &config, "trr")).unwrap();
+
+        assert!(explanation["alias"].is_null());
+        assert_eq!(explanation["branch"], "fix/typo");
+    }
+}
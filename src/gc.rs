@@ -0,0 +1,202 @@
+use crate::config;
+use crate::create::{confirm, sanitize_tmux_name, tmux_target_name};
+use crate::delete::{Repository, get_repositories};
+use crate::git::get_repo_prefix;
+use crate::prune::find_stale;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Hidden entries hold trr's own state (.trr-sys) and staging directories
+fn find_orphan_dirs(repositories: &[Repository], entries: &[String]) -> Vec<String> {
+    // Unsanitized directories are nested, so only their top-level entry is listed
+    let known: HashSet<&str> = repositories
+        .iter()
+        .filter_map(|repo| repo.directory.split('/').next())
+        .collect();
+    entries
+        .iter()
+        .filter(|entry| !entry.starts_with('.') && !known.contains(entry.as_str()))
+        .cloned()
+        .collect()
+}
+
+// A session is kept as long as either its metadata or its directory remains
+fn find_orphan_sessions(
+    sessions: &str,
+    prefix: &str,
+    repositories: &[Repository],
+    dirs: &[String],
+) -> Vec<String> {
+    sessions
+        .lines()
+        .filter(|session| {
+            // Compared sanitized, so sessions named before sanitizing still match
+            session.strip_prefix(prefix).is_some_and(|name| {
+                let name = sanitize_tmux_name(name);
+                !repositories
+                    .iter()
+                    .any(|repo| sanitize_tmux_name(&repo.metadata.branch) == name)
+                    && !dirs.iter().any(|dir| sanitize_tmux_name(dir) == name)
+            })
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn list_dirs(repo_sync_path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !repo_sync_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut dirs: Vec<String> = fs::read_dir(repo_sync_path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn list_tmux_sessions() -> String {
+    Command::new("tmux")
+        .arg("list-sessions")
+        .arg("-F")
+        .arg("#{session_name}")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .unwrap_or_default()
+}
+
+fn confirm_category(
+    title: &str,
+    items: &[String],
+    dry_run: bool,
+    yes: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if items.is_empty() {
+        return Ok(false);
+    }
+
+    println!("{title}:");
+    for item in items {
+        println!("  {item}");
+    }
+
+    if dry_run {
+        return Ok(false);
+    }
+    Ok(yes || confirm(&format!("Remove these {} entries?", items.len()))?)
+}
+
+pub fn gc_repos(dry_run: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+    let repo_sync_path = PathBuf::from(&config.settings.repo_sync_path);
+    let mut removed = 0;
+
+    let stale = find_stale(&repositories, &repo_sync_path);
+    let stale_items: Vec<String> = stale
+        .iter()
+        .map(|repo| format!("{}\t{}", repo.ulid, repo.metadata.branch))
+        .collect();
+    if confirm_category("Metadata without a directory", &stale_items, dry_run, yes)? {
+        for repo in &stale {
+            fs::remove_file(&repo.path)?;
+            removed += 1;
+        }
+    }
+
+    let dirs = list_dirs(&repo_sync_path)?;
+    let orphan_dirs = find_orphan_dirs(&repositories, &dirs);
+    if confirm_category("Directories without metadata", &orphan_dirs, dry_run, yes)? {
+        for dir in &orphan_dirs {
+            fs::remove_dir_all(repo_sync_path.join(dir))?;
+            removed += 1;
+        }
+    }
+
+    // Only sessions named after this repository are considered
+    let prefix = tmux_target_name(
+        &get_repo_prefix(&config),
+        &config.settings.name_separator,
+        "",
+    );
+    let orphan_sessions =
+        find_orphan_sessions(&list_tmux_sessions(), &prefix, &repositories, &dirs);
+    if confirm_category(
+        "tmux sessions without a copy",
+        &orphan_sessions,
+        dry_run,
+        yes,
+    )? {
+        for session in &orphan_sessions {
+            Command::new("tmux")
+                .arg("kill-session")
+                .arg("-t")
+                .arg(session)
+                .status()?;
+            removed += 1;
+        }
+    }
+
+    let found = stale_items.len() + orphan_dirs.len() + orphan_sessions.len();
+    if found == 0 {
+        println!("Nothing to clean up.");
+    } else if dry_run {
+        println!("Found {found} entries to clean up (dry run).");
+    } else {
+        println!("Removed {removed} of {found} entries.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create::RepositoryMetadata;
+
+    fn repository(branch: &str, directory: &str) -> Repository {
+        Repository {
+            directory: directory.to_string(),
+            metadata: RepositoryMetadata {
+                branch: branch.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_orphan_dirs() {
+        let repositories = vec![
+            repository("feature/a", "feature-a"),
+            repository("fix/nested", "fix/nested"),
+        ];
+        let entries = vec![
+            ".trr-sys".to_string(),
+            "feature-a".to_string(),
+            "feature-b".to_string(),
+            "fix".to_string(),
+        ];
+
+        assert_eq!(
+            find_orphan_dirs(&repositories, &entries),
+            vec!["feature-b".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_find_orphan_sessions() {
+        let repositories = vec![repository("feature/a", "")];
+        let dirs = vec!["feature-b".to_string()];
+        let sessions = "trr-feature/a\ntrr-feature-a\ntrr-feature/b\ntrr-feature/c\nother\n";
+
+        assert_eq!(
+            find_orphan_sessions(sessions, "trr-", &repositories, &dirs),
+            vec!["trr-feature/c".to_string()]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::config::Config;
+use std::path::Path;
+use std::process::Command;
+
+pub fn current_branch(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg("HEAD")
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn get_repo_name() -> Option<String> {
+    let output = Command::new("git")
+        .arg("remote")
+        .arg("get-url")
+        .arg("origin")
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let repo_name = if url.starts_with("https://") || url.starts_with("http://") {
+        url.split('/')
+            .next_back()?
+            .trim_end_matches(".git")
+            .to_string()
+    } else if url.contains(':') {
+        url.split(':')
+            .next_back()?
+            .split('/')
+            .next_back()?
+            .trim_end_matches(".git")
+            .to_string()
+    } else {
+        return None;
+    };
+
+    Some(repo_name)
+}
+
+pub fn get_source_repo_name() -> String {
+    get_repo_name()
+        .or_else(|| {
+            std::env::current_dir().ok().and_then(|dir| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+        })
+        .unwrap_or_else(|| "trr".to_string())
+}
+
+// A length of 0 keeps the full repository name
+fn truncate_repo_prefix(repo_name: &str, prefix_length: usize) -> String {
+    if prefix_length == 0 {
+        repo_name.to_string()
+    } else {
+        repo_name.chars().take(prefix_length).collect()
+    }
+}
+
+pub fn get_repo_prefix(config: &Config) -> String {
+    if let Some(repo_prefix) = &config.settings.repo_prefix {
+        return repo_prefix.clone();
+    }
+    truncate_repo_prefix(&get_source_repo_name(), config.settings.repo_prefix_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_repo_prefix() {
+        // This test ensures the function runs and returns a string
+        let prefix = get_repo_prefix(&Config::default());
+        assert!(!prefix.is_empty());
+        assert!(prefix.len() <= 3);
+    }
+
+    #[test]
+    fn test_get_repo_prefix_override() {
+        let mut config = Config::default();
+        config.settings.repo_prefix = Some("mono".to_string());
+        config.settings.repo_prefix_length = 2;
+        assert_eq!(get_repo_prefix(&config), "mono");
+    }
+
+    #[test]
+    fn test_truncate_repo_prefix() {
+        assert_eq!(truncate_repo_prefix("webapp-frontend", 3), "web");
+        assert_eq!(truncate_repo_prefix("webapp-frontend", 8), "webapp-f");
+        assert_eq!(
+            truncate_repo_prefix("webapp-frontend", 0),
+            "webapp-frontend"
+        );
+        assert_eq!(truncate_repo_prefix("api", 10), "api");
+    }
+}
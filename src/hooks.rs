@@ -0,0 +1,169 @@
+use crate::config::Config;
+use crate::config::expand_tilde;
+use crate::create::{expand_branch_placeholders, substitute_args};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct HookContext<'a> {
+    pub branch: &'a str,
+    pub dir: &'a Path,
+    pub ulid: &'a str,
+}
+
+fn find_hooks(hooks_dir: &Path, event: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if !hooks_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut hooks: Vec<PathBuf> = fs::read_dir(hooks_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(event))
+        })
+        .collect();
+    hooks.sort();
+    Ok(hooks)
+}
+
+// Failures in pre-* hooks abort the operation, post-* hooks only warn
+pub fn run_hooks(
+    config: &Config,
+    event: &str,
+    context: &HookContext,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(hooks_dir) = config.settings.hooks_dir.as_deref() else {
+        return Ok(());
+    };
+    let abort_on_failure = event.starts_with("pre-");
+
+    for hook in find_hooks(&expand_tilde(hooks_dir), event)? {
+        // stdout goes to stderr so hooks can't interfere with --print-session output
+        let result = Command::new(&hook)
+            .env("TRR_BRANCH", context.branch)
+            .env("TRR_DIR", context.dir)
+            .env("TRR_ULID", context.ulid)
+            .env("TRR_EVENT", event)
+            .stdout(io::stderr())
+            .status();
+
+        let error = match result {
+            Ok(status) if status.success() => continue,
+            Ok(status) => format!("Hook '{}' failed with {status}", hook.display()),
+            Err(e) => format!("Failed to run hook '{}': {e}", hook.display()),
+        };
+
+        if abort_on_failure {
+            return Err(error.into());
+        }
+        eprintln!("Warning: {error}");
+    }
+
+    Ok(())
+}
+
+// Runs in the source before anything is copied, so a failure aborts the creation
+pub fn run_pre_create_command(
+    config: &Config,
+    context: &HookContext,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(command) = config.settings.pre_create_command.as_deref() else {
+        return Ok(());
+    };
+    let command = substitute_args(
+        &expand_branch_placeholders(command, context.branch, context.dir),
+        &args.join(" "),
+    )?;
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .stdout(io::stderr())
+        .status()
+        .map_err(|e| format!("Failed to run pre_create_command '{command}': {e}"))?;
+    if !status.success() {
+        return Err(format!("pre_create_command '{command}' failed with {status}").into());
+    }
+    Ok(())
+}
+
+// Runs after the copy and its metadata are gone, so a failure only warns
+pub fn run_post_delete_command(config: &Config, context: &HookContext) {
+    let Some(command) = config.settings.post_delete_command.as_deref() else {
+        return;
+    };
+    let command = expand_branch_placeholders(command, context.branch, context.dir);
+
+    match Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .stdout(io::stderr())
+        .status()
+    {
+        Ok(status) if status.success() => {
+            println!("post_delete_command finished with {status}");
+        }
+        Ok(status) => eprintln!("Warning: post_delete_command '{command}' failed with {status}"),
+        Err(e) => eprintln!("Warning: failed to run post_delete_command '{command}': {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ulid::Ulid;
+
+    #[test]
+    fn test_find_hooks_in_lexical_order() {
+        let test_id = Ulid::new().to_string();
+        let hooks_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(&hooks_dir).unwrap();
+        for name in [
+            "pre-create-20-deps",
+            "pre-create",
+            "pre-create-10-env",
+            "post-create",
+        ] {
+            fs::write(hooks_dir.join(name), "#!/bin/sh\n").unwrap();
+        }
+
+        let hooks: Vec<String> = find_hooks(&hooks_dir, "pre-create")
+            .unwrap()
+            .iter()
+            .map(|hook| hook.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            hooks,
+            vec!["pre-create", "pre-create-10-env", "pre-create-20-deps"]
+        );
+        assert!(find_hooks(&hooks_dir, "pre-delete").unwrap().is_empty());
+
+        // Clean up
+        let _ = fs::remove_dir_all(&hooks_dir);
+    }
+
+    #[test]
+    fn test_run_pre_create_command() {
+        let mut config = Config::default();
+        let context = HookContext {
+            branch: "feature/a",
+            dir: Path::new("/tmp/feature-a"),
+            ulid: "01ARZ3NDEKTSV4RRFFQ69G5FAV",
+        };
+        assert!(run_pre_create_command(&config, &context, &[]).is_ok());
+
+        config.settings.pre_create_command =
+            Some("test '@@branch @@dir @@args' = 'feature/a /tmp/feature-a x'".to_string());
+        assert!(run_pre_create_command(&config, &context, &["x".to_string()]).is_ok());
+        assert!(run_pre_create_command(&config, &context, &[]).is_err());
+
+        config.settings.pre_create_command = Some("echo @@args!".to_string());
+        assert!(run_pre_create_command(&config, &context, &[]).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::config;
+use crate::delete::{Repository, find_repository, get_repositories};
+use std::path::Path;
+
+fn format_info(repo: &Repository, copy_path: &Path) -> Vec<String> {
+    let mut lines = vec![
+        format!("ULID: {}", repo.ulid),
+        format!("Branch: {}", repo.metadata.branch),
+        format!("Directory: {}", repo.directory),
+        format!(
+            "Created at: {}",
+            repo.metadata.created_at.format("%Y-%m-%d %H:%M:%S")
+        ),
+        format!(
+            "Source path: {}",
+            repo.metadata.source_path.as_deref().unwrap_or("(unknown)")
+        ),
+    ];
+    if repo.metadata.branch_only {
+        lines.push("Copy path: (branch only, no copy)".to_string());
+    } else {
+        lines.push(format!("Copy path: {}", copy_path.display()));
+    }
+
+    let optional = [
+        ("Source commit", repo.metadata.source_commit.clone()),
+        ("Repository", repo.metadata.repo_name.clone()),
+        ("Group", repo.metadata.group.clone()),
+        ("Label", repo.metadata.label.clone()),
+        ("Alias", repo.metadata.alias.clone()),
+        ("Session group", repo.metadata.session_group.clone()),
+        (
+            "Pull request",
+            repo.metadata.pr_number.map(|number| format!("#{number}")),
+        ),
+        (
+            "Args",
+            (!repo.metadata.args.is_empty()).then(|| repo.metadata.args.join(" ")),
+        ),
+        (
+            "Copy strategy",
+            repo.metadata.worktree.then(|| "worktree".to_string()),
+        ),
+        (
+            "Create duration",
+            repo.metadata
+                .create_duration_ms
+                .map(|ms| format!("{ms} ms")),
+        ),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            lines.push(format!("{name}: {value}"));
+        }
+    }
+    for linked_path in &repo.metadata.linked_paths {
+        lines.push(format!("Linked: {linked_path}"));
+    }
+    lines
+}
+
+pub fn print_info(branch: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+
+    let Some(repo) = find_repository(&repositories, branch)? else {
+        println!("No repository selected.");
+        return Ok(());
+    };
+
+    let copy_path = std::env::current_dir()?
+        .join(&config.settings.repo_sync_path)
+        .join(&repo.directory);
+    for line in format_info(&repo, &copy_path) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create::RepositoryMetadata;
+
+    #[test]
+    fn test_format_info() {
+        let repo = Repository {
+            ulid: "01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string(),
+            directory: "feature-a".to_string(),
+            metadata: RepositoryMetadata {
+                branch: "feature/a".to_string(),
+                source_path: Some("/src/trr".to_string()),
+                group: Some("epic-42".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let lines = format_info(&repo, Path::new("/src/trr/.trr/feature-a"));
+        assert_eq!(lines[0], "ULID: 01ARZ3NDEKTSV4RRFFQ69G5FAV");
+        assert!(lines.contains(&"Source path: /src/trr".to_string()));
+        assert!(lines.contains(&"Copy path: /src/trr/.trr/feature-a".to_string()));
+        assert!(lines.contains(&"Group: epic-42".to_string()));
+        assert!(!lines.iter().any(|line| line.starts_with("Label")));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::config;
+use crate::create::RepositoryMetadata;
+use crate::delete::{Repository, get_repositories, load_sizes};
+use crate::git::get_source_repo_name;
+use crate::output::{self, human_size};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const UNKNOWN_REPO: &str = "(unknown)";
+
+#[derive(Serialize)]
+struct ListEntry {
+    ulid: String,
+    #[serde(flatten)]
+    metadata: RepositoryMetadata,
+}
+
+impl From<&Repository> for ListEntry {
+    fn from(repo: &Repository) -> Self {
+        ListEntry {
+            ulid: repo.ulid.clone(),
+            // The ULID is already the top-level field of the entry
+            metadata: RepositoryMetadata {
+                ulid: None,
+                directory: Some(repo.directory.clone()),
+                ..repo.metadata.clone()
+            },
+        }
+    }
+}
+
+fn format_notes(repo: &Repository) -> String {
+    let mut notes = String::new();
+    if repo.metadata.branch_only {
+        notes.push_str(" (branch only)");
+    }
+    if let Some(group) = &repo.metadata.group {
+        notes.push_str(&format!(" [{group}]"));
+    }
+    notes
+}
+
+const SHORT_COMMIT_LENGTH: usize = 7;
+// Fits human_size output up to "1023M"
+const SIZE_WIDTH: usize = 5;
+
+// Copies created before source commits were recorded show "-"
+fn short_commit(commit: Option<&str>) -> &str {
+    commit.map_or("-", |commit| {
+        commit.get(..SHORT_COMMIT_LENGTH).unwrap_or(commit)
+    })
+}
+
+fn format_table(repositories: &[&Repository]) -> Vec<String> {
+    let branch_width = repositories
+        .iter()
+        .map(|repo| repo.metadata.branch.len())
+        .chain(["BRANCH".len()])
+        .max()
+        .unwrap_or_default();
+    let directory_width = repositories
+        .iter()
+        .map(|repo| repo.directory.len())
+        .chain(["DIRECTORY".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![format!(
+        "{:<19}  {:>SIZE_WIDTH$}  {:<branch_width$}  {:<directory_width$}  {:<SHORT_COMMIT_LENGTH$}  ULID",
+        "CREATED", "SIZE", "BRANCH", "DIRECTORY", "SOURCE"
+    )];
+    for repo in repositories {
+        let line = format!(
+            "{}  {:>SIZE_WIDTH$}  {:<branch_width$}  {:<directory_width$}  {:<SHORT_COMMIT_LENGTH$}  {}{}",
+            repo.metadata.created_at.format("%Y-%m-%d %H:%M:%S"),
+            repo.size.map_or("-".to_string(), human_size),
+            repo.metadata.branch,
+            repo.directory,
+            short_commit(repo.metadata.source_commit.as_deref()),
+            repo.ulid,
+            format_notes(repo)
+        );
+        lines.push(line.trim_end().to_string());
+    }
+    lines
+}
+
+fn group_by_repo<'a>(repositories: &[&'a Repository]) -> BTreeMap<&'a str, Vec<&'a Repository>> {
+    let mut groups: BTreeMap<&str, Vec<&Repository>> = BTreeMap::new();
+    for repo in repositories {
+        groups
+            .entry(repo.metadata.repo_name.as_deref().unwrap_or(UNKNOWN_REPO))
+            .or_default()
+            .push(repo);
+    }
+    groups
+}
+
+pub fn list_repos(
+    global: bool,
+    group: Option<&str>,
+    json: bool,
+    json_pretty: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let mut repositories = get_repositories(&config)?;
+    repositories.sort_by_key(|repo| repo.metadata.created_at);
+    if let Some(group) = group {
+        repositories.retain(|repo| repo.metadata.group.as_deref() == Some(group));
+    }
+
+    // Copies recorded before repo names were stored are assumed to belong here
+    let current_repo = (!global).then(get_source_repo_name);
+    repositories.retain(|repo| match (&current_repo, &repo.metadata.repo_name) {
+        (Some(current_repo), Some(name)) => name == current_repo,
+        _ => true,
+    });
+
+    if json {
+        let entries: Vec<ListEntry> = repositories.iter().map(ListEntry::from).collect();
+        println!("{}", output::to_json(&entries, json_pretty)?);
+        return Ok(());
+    }
+
+    // Only the table shows sizes, so JSON output doesn't pay for walking every copy
+    load_sizes(
+        Path::new(&config.settings.repo_sync_path),
+        &mut repositories,
+    );
+    let repositories: Vec<&Repository> = repositories.iter().collect();
+
+    if repositories.is_empty() {
+        println!("No repository copies found");
+        return Ok(());
+    }
+
+    if global {
+        for (repo_name, repos) in group_by_repo(&repositories) {
+            println!("{repo_name}:");
+            for line in format_table(&repos) {
+                println!("  {line}");
+            }
+        }
+        return Ok(());
+    }
+
+    for line in format_table(&repositories) {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository(branch: &str, repo_name: Option<&str>) -> Repository {
+        Repository {
+            directory: branch.replace('/', "-"),
+            metadata: RepositoryMetadata {
+                branch: branch.to_string(),
+                repo_name: repo_name.map(str::to_string),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_table_aligns_columns() {
+        let mut repo = repository("feature/a", None);
+        repo.ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string();
+        repo.metadata.group = Some("epic-42".to_string());
+        repo.metadata.source_commit = Some("3f9c2a1d8e7b6a5f4e3d2c1b0a9f8e7d6c5b4a39".to_string());
+        repo.size = Some(12 * 1024);
+        let long = repository("feature/longer-name", None);
+
+        let lines = format_table(&[&repo, &long]);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("CREATED"));
+        assert_eq!(lines[0].find("DIRECTORY"), lines[1].find("feature-a"));
+        assert_eq!(lines[0].find("SIZE"), lines[1].find("  12K").map(|i| i + 1));
+        assert_eq!(lines[0].find("SIZE").map(|i| i + 3), lines[2].find("- "));
+        assert_eq!(lines[0].find("SOURCE"), lines[1].find("3f9c2a1 "));
+        assert_eq!(lines[0].find("SOURCE"), lines[2].rfind('-'));
+        assert!(lines[1].ends_with("01ARZ3NDEKTSV4RRFFQ69G5FAV [epic-42]"));
+    }
+
+    #[test]
+    fn test_list_entry_json_includes_ulid() {
+        let mut repo = repository("feature/a", None);
+        repo.ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string();
+
+        let json = serde_json::to_value(ListEntry::from(&repo)).unwrap();
+        assert_eq!(json["ulid"], "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+        assert_eq!(json["branch"], "feature/a");
+        assert_eq!(json["directory"], "feature-a");
+    }
+
+    #[test]
+    fn test_group_by_repo() {
+        let repositories = vec![
+            repository("feature/a", Some("trr")),
+            repository("feature/b", Some("api")),
+            repository("feature/c", Some("trr")),
+            repository("legacy", None),
+        ];
+
+        let repositories: Vec<&Repository> = repositories.iter().collect();
+        let groups = group_by_repo(&repositories);
+        let names: Vec<_> = groups.keys().copied().collect();
+        assert_eq!(names, vec!["(unknown)", "api", "trr"]);
+        assert_eq!(groups["trr"].len(), 2);
+        assert_eq!(groups["api"][0].metadata.branch, "feature/b");
+    }
+}
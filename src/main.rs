@@ -1,8 +1,26 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 
+mod clean;
 mod config;
 mod create;
 mod delete;
+mod diff;
+mod explain;
+mod gc;
+mod git;
+mod hooks;
+mod info;
+mod list;
+mod output;
+mod prune;
+mod rename;
+mod restore;
+mod stats;
+mod switch;
+mod sync;
+mod template;
+mod watch;
 
 const APP_VERSION: &str = concat!(
     env!("CARGO_PKG_NAME"),
@@ -23,8 +41,24 @@ struct Cli {
 
     #[arg(long, short = 'V', help = "Print version")]
     version: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Load <name>.toml from the config directory over the base config (same as TRR_PROFILE)"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Pretty-print JSON output (compact by default)"
+    )]
+    json_pretty: bool,
 }
 
+// Parsed once per run, so Create's many flags outweighing the other variants is fine
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     #[command(alias = "c")]
@@ -32,7 +66,8 @@ enum Commands {
         about = "Create a new repository copy using rsync and set up a tmux session/window (alias: c)"
     )]
     Create {
-        branch: String,
+        #[arg(required_unless_present = "from_pr")]
+        branch: Option<String>,
 
         #[arg(trailing_var_arg = true)]
         #[arg(help = "Arguments to pass to tmux initialization commands")]
@@ -40,14 +75,450 @@ enum Commands {
 
         #[arg(long, help = "Enable debug output including rsync verbose logs")]
         debug: bool,
+
+        #[arg(
+            long,
+            help = "Symlink settings.link_paths in the copy back to the source instead of copying them"
+        )]
+        link_config: bool,
+
+        #[arg(
+            long,
+            value_name = "SECS",
+            help = "Abort rsync when no data is transferred for this many seconds"
+        )]
+        rsync_timeout: Option<u64>,
+
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            help = "Copy only files matching this rsync include pattern (repeatable); a directory copies all its contents (dir/***)"
+        )]
+        only: Vec<String>,
+
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            help = "Copy files matching this rsync pattern even if rsync_excludes or .gitignore drop them (repeatable)"
+        )]
+        copy_ignored: Vec<String>,
+
+        #[arg(
+            long,
+            help = "List the files rsync would copy (after excludes) and exit without copying"
+        )]
+        list_files: bool,
+
+        #[arg(
+            long,
+            help = "Create the branch in the current repository without copying or tmux setup"
+        )]
+        branch_only: bool,
+
+        #[arg(
+            long,
+            value_name = "BOOL",
+            help = "Preserve source mtimes in the copy; --preserve-mtime=false gives fresh mtimes"
+        )]
+        preserve_mtime: Option<bool>,
+
+        #[arg(
+            long,
+            help = "Treat tmux setup as best-effort: keep the copy and report tmux errors at the end"
+        )]
+        continue_on_tmux_error: bool,
+
+        #[arg(
+            long,
+            help = "Prefix init commands with a space to keep them out of shell history"
+        )]
+        quiet_tmux: bool,
+
+        #[arg(
+            long = "init-commands-from-template-engine",
+            help = "Render init commands as a template with {{args}}, {{branch}}, {{dir}} and {{#if}}"
+        )]
+        template_engine: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "branch_only",
+            help = "Print only the tmux session/window name to stdout and leave a new session detached"
+        )]
+        print_session: bool,
+
+        #[arg(
+            long,
+            help = "Show how many files and bytes rsync would copy and ask before copying"
+        )]
+        rsync_dry_then_confirm: bool,
+
+        #[arg(long, short = 'y', help = "Answer yes to confirmation prompts")]
+        yes: bool,
+
+        #[arg(
+            long,
+            help = "Create the tmux session detached without attaching, even without a TTY"
+        )]
+        detach: bool,
+
+        #[arg(
+            long,
+            alias = "ulid-prefix",
+            value_name = "NAME",
+            help = "Tag the copy with a group so related copies can be listed and cleaned together"
+        )]
+        group: Option<String>,
+
+        #[arg(
+            long,
+            help = "After copying, keep re-syncing source changes into the copy until Ctrl-C"
+        )]
+        watch: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Create the tmux window at this index when running inside tmux, moving a window already there up by one"
+        )]
+        window_index: Option<u32>,
+
+        #[arg(
+            long,
+            value_name = "NUMBER",
+            conflicts_with = "branch_only",
+            help = "Copy the repository and check out the head branch of this pull request (requires gh)"
+        )]
+        from_pr: Option<u64>,
+
+        #[arg(
+            long,
+            help = "Print how long rsync, git, tmux and the whole create took (also with --debug)"
+        )]
+        timings: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "branch_only",
+            help = "Print the resolved branch, target directory, rsync command and tmux name without creating anything"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "from_pr",
+            help = "Pick the local branch or tag to create the branch from"
+        )]
+        pick_base: bool,
+
+        #[arg(
+            long,
+            help = "Use the branch name verbatim as the directory name (slashes create nested directories)"
+        )]
+        no_sanitize_dir: bool,
+
+        #[arg(
+            long,
+            value_name = "TEXT",
+            help = "Short label stored in metadata and set as the tmux window option @trr_label"
+        )]
+        label: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with = "branch_only",
+            help = "Continue an interrupted copy in the target directory instead of failing, and keep a partial copy if rsync fails"
+        )]
+        resume: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "session",
+            help = "Open the copy as a window in the current session (overrides settings.tmux_mode)"
+        )]
+        window: bool,
+
+        #[arg(
+            long,
+            help = "Open the copy in a new session even inside tmux (overrides settings.tmux_mode)"
+        )]
+        session: bool,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with_all = ["window", "branch_only"],
+            help = "Open the copy as a session in this tmux session group (implies --session)"
+        )]
+        session_group: Option<String>,
+
+        #[arg(
+            long,
+            help = "Allow branch names with whitespace or shell/tmux special characters"
+        )]
+        force: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "no_tmux",
+            help = "When the copy already exists and its tmux window/session is open, close and reopen it with the init commands instead of switching to it"
+        )]
+        no_reuse: bool,
+
+        #[arg(
+            long,
+            value_name = "PREFIX",
+            help = "Prefix the copy's directory name, e.g. 20240101- (overrides settings.dir_prefix_template)"
+        )]
+        dir_prefix: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["window", "session", "session_group", "print_session"],
+            help = "Only create the copy and branch, then print its path instead of opening tmux"
+        )]
+        no_tmux: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "branch_only",
+            help = "Run `git submodule update --init --recursive` in the copy after checking out the branch"
+        )]
+        mirror_submodules: bool,
     },
 
     #[command(about = "Open the config file in your editor or create it with defaults (no alias)")]
-    Config,
+    Config {
+        #[arg(
+            long,
+            help = "Print the JSON Schema of the config file instead of opening it"
+        )]
+        schema: bool,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with = "schema",
+            help = "Merge settings and branch_aliases from another TOML file into the config"
+        )]
+        merge: Option<std::path::PathBuf>,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["schema", "merge"],
+            help = "Print the resolved config file path and whether it exists"
+        )]
+        path: bool,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["schema", "merge", "path"],
+            help = "Print a default config with optional settings commented out and explained"
+        )]
+        template: bool,
+    },
 
     #[command(alias = "d")]
     #[command(about = "Select and delete repository copies using fuzzy search (alias: d)")]
-    Delete,
+    Delete {
+        #[arg(help = "Branch of the copy to delete (select interactively when omitted)")]
+        branch: Option<String>,
+
+        #[arg(
+            long,
+            help = "Also delete the git branch of --branch-only and worktree copies from the source repository (asks first unless --yes)"
+        )]
+        delete_branch: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "delete_branch",
+            help = "Keep the git branch even when delete_branch = true (no effect on rsync copies)"
+        )]
+        keep_branch: bool,
+
+        #[arg(
+            long,
+            help = "Require typing the branch name to confirm instead of y/N (delete_confirm = \"typed\")"
+        )]
+        confirm_branch: bool,
+
+        #[arg(long, short = 'y', help = "Skip the confirmation prompt")]
+        yes: bool,
+    },
+
+    #[command(alias = "l")]
+    #[command(about = "List repository copies of the current repository (alias: l)")]
+    List {
+        #[arg(
+            long,
+            help = "List copies of every repository in repo_sync_path, grouped by repository"
+        )]
+        global: bool,
+
+        #[arg(long, value_name = "NAME", help = "Only list copies in this group")]
+        group: Option<String>,
+
+        #[arg(long, help = "Print the repository metadata as a JSON array")]
+        json: bool,
+    },
+
+    #[command(about = "Delete repository copies by group and/or age (no alias)")]
+    Clean {
+        #[arg(
+            long,
+            value_name = "NAME",
+            required_unless_present = "older_than",
+            help = "Group to delete"
+        )]
+        group: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "AGE",
+            value_parser = clean::parse_age,
+            help = "Only delete copies created longer ago than this (e.g. 30d, 12h, 2w)"
+        )]
+        older_than: Option<chrono::TimeDelta>,
+
+        #[arg(
+            long,
+            help = "Show each copy's size and last commit date, and the total reclaimable space"
+        )]
+        preview: bool,
+
+        #[arg(long, short = 'y', help = "Skip the confirmation prompt")]
+        yes: bool,
+    },
+
+    #[command(
+        about = "Refresh a repository copy's files from its source, keeping its branch (no alias)"
+    )]
+    Sync {
+        #[arg(help = "Branch of the copy to sync (select interactively when omitted)")]
+        branch: Option<String>,
+
+        #[arg(
+            long,
+            help = "List the files that would be transferred without syncing"
+        )]
+        dry_run: bool,
+
+        #[arg(long, short = 'y', help = "Skip the confirmation prompt")]
+        yes: bool,
+    },
+
+    #[command(
+        about = "Print how a branch name expands through aliases as JSON, without creating anything (no alias)"
+    )]
+    Explain { branch: String },
+
+    #[command(about = "List files changed in a repository copy relative to its source (no alias)")]
+    Diff {
+        branch: String,
+
+        #[arg(
+            long,
+            help = "Compare against the commit the copy was created from instead of the current source"
+        )]
+        commit: bool,
+    },
+
+    #[command(
+        about = "Reconcile metadata, copy directories and tmux sessions, removing orphans (no alias)"
+    )]
+    Gc {
+        #[arg(long, help = "Show what would be removed without removing anything")]
+        dry_run: bool,
+
+        #[arg(long, short = 'y', help = "Skip the confirmation prompts")]
+        yes: bool,
+    },
+
+    #[command(alias = "s")]
+    #[command(
+        about = "Switch to a repository copy's tmux session/window, recreating it when closed (alias: s)"
+    )]
+    Switch {
+        #[arg(help = "Branch of the copy to switch to (select interactively when omitted)")]
+        branch: Option<String>,
+    },
+
+    #[command(
+        about = "Select a copy's existing tmux window, attaching when outside tmux (no alias)"
+    )]
+    Focus {
+        #[arg(help = "Branch of the copy to focus")]
+        branch: String,
+    },
+
+    #[command(
+        about = "Rename a copy's branch, directory and tmux session/window without copying again (no alias)"
+    )]
+    Rename {
+        #[arg(help = "Branch of the copy to rename")]
+        old_branch: String,
+
+        #[arg(help = "New branch name")]
+        new_branch: String,
+
+        #[arg(
+            long,
+            help = "Use the new branch name even if it contains characters unsafe for tmux or shells"
+        )]
+        force: bool,
+    },
+
+    #[command(about = "Show a copy's metadata, including where it was created from (no alias)")]
+    Info {
+        #[arg(help = "Branch of the copy to inspect (select interactively when omitted)")]
+        branch: Option<String>,
+    },
+
+    #[command(
+        about = "Summarize copies in repo_sync_path: count, disk usage, ages, branch prefixes and groups (no alias)"
+    )]
+    Stats {
+        #[arg(long, help = "Print the statistics as JSON")]
+        json: bool,
+    },
+
+    #[command(about = "Remove metadata of copies whose directory no longer exists (no alias)")]
+    Prune {
+        #[arg(long, help = "Show what would be removed without removing anything")]
+        dry_run: bool,
+    },
+
+    #[command(
+        about = "Restore a repository copy from a tarball in settings.archive_dir (no alias)"
+    )]
+    Restore {
+        #[arg(long, help = "Open a tmux session/window for the restored copy")]
+        tmux: bool,
+    },
+
+    #[command(hide = true)]
+    #[command(about = "Print a shell completion script to stdout (no alias)")]
+    Completions {
+        #[arg(value_enum, help = "Shell to generate completions for")]
+        shell: Shell,
+    },
+}
+
+// clap fills the optional branch first, so with --from-pr (where the branch comes from
+// the PR) that first value is an init arg too
+fn split_create_positionals(
+    branch: Option<String>,
+    mut args: Vec<String>,
+    from_pr: Option<u64>,
+) -> (String, Vec<String>) {
+    match (from_pr, branch) {
+        (Some(_), Some(first)) => {
+            args.insert(0, first);
+            (String::new(), args)
+        }
+        (_, branch) => (branch.unwrap_or_default(), args),
+    }
 }
 
 fn main() {
@@ -58,30 +529,236 @@ fn main() {
         std::process::exit(0);
     }
 
+    if let Some(profile) = &cli.profile {
+        // SAFETY: set before any other threads are spawned
+        unsafe {
+            std::env::set_var("TRR_PROFILE", profile);
+        }
+    }
+
     match cli.command {
         Some(command) => match command {
             Commands::Create {
                 branch,
                 args,
                 debug,
+                link_config,
+                rsync_timeout,
+                only,
+                copy_ignored,
+                list_files,
+                branch_only,
+                preserve_mtime,
+                continue_on_tmux_error,
+                quiet_tmux,
+                template_engine,
+                print_session,
+                rsync_dry_then_confirm,
+                yes,
+                detach,
+                group,
+                watch,
+                window_index,
+                from_pr,
+                timings,
+                dry_run,
+                pick_base,
+                no_sanitize_dir,
+                label,
+                resume,
+                window,
+                session,
+                session_group,
+                force,
+                no_reuse,
+                dir_prefix,
+                no_tmux,
+                mirror_submodules,
             } => {
-                if let Err(e) = create::create_repo(&branch, &args, debug) {
+                let (branch, args) = split_create_positionals(branch, args, from_pr);
+                let options = create::CreateOptions {
+                    debug,
+                    link_config,
+                    rsync_timeout,
+                    only,
+                    copy_ignored,
+                    list_files,
+                    branch_only,
+                    preserve_mtime,
+                    continue_on_tmux_error,
+                    quiet_tmux,
+                    template_engine,
+                    print_session,
+                    rsync_dry_then_confirm,
+                    yes,
+                    detach,
+                    group,
+                    watch,
+                    window_index,
+                    from_pr,
+                    timings,
+                    dry_run,
+                    pick_base,
+                    no_sanitize_dir,
+                    label,
+                    resume,
+                    tmux_mode: if window {
+                        Some(config::TmuxMode::Window)
+                    } else if session || session_group.is_some() {
+                        Some(config::TmuxMode::Session)
+                    } else {
+                        None
+                    },
+                    session_group,
+                    force,
+                    no_reuse,
+                    dir_prefix,
+                    no_tmux,
+                    mirror_submodules,
+                };
+                if let Err(e) = create::create_repo(&branch, &args, &options) {
                     eprintln!("Error creating repository: {e}");
                     std::process::exit(1);
                 }
             }
-            Commands::Config => {
-                if let Err(e) = config::init_config() {
+            Commands::Config {
+                schema,
+                merge,
+                path,
+                template,
+            } => {
+                let result = if template {
+                    config::print_template()
+                } else if path {
+                    config::print_config_path()
+                } else if schema {
+                    config::print_schema(cli.json_pretty)
+                } else if let Some(merge) = merge {
+                    config::merge_config(&merge)
+                } else {
+                    config::init_config()
+                };
+                if let Err(e) = result {
                     eprintln!("Error initializing config: {e}");
                     std::process::exit(1);
                 }
             }
-            Commands::Delete => {
-                if let Err(e) = delete::delete_repo() {
+            Commands::Delete {
+                branch,
+                delete_branch,
+                keep_branch,
+                confirm_branch,
+                yes,
+            } => {
+                if let Err(e) = delete::delete_repo(
+                    branch.as_deref(),
+                    delete_branch,
+                    keep_branch,
+                    confirm_branch,
+                    yes,
+                ) {
                     eprintln!("Error deleting repository: {e}");
                     std::process::exit(1);
                 }
             }
+            Commands::List {
+                global,
+                group,
+                json,
+            } => {
+                if let Err(e) = list::list_repos(global, group.as_deref(), json, cli.json_pretty) {
+                    eprintln!("Error listing repositories: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Clean {
+                group,
+                older_than,
+                preview,
+                yes,
+            } => {
+                if let Err(e) = clean::clean_repos(group.as_deref(), older_than, preview, yes) {
+                    eprintln!("Error cleaning repositories: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Sync {
+                branch,
+                dry_run,
+                yes,
+            } => {
+                if let Err(e) = sync::sync_repo(branch.as_deref(), dry_run, yes) {
+                    eprintln!("Error syncing repository: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Explain { branch } => {
+                if let Err(e) = explain::explain_alias(&branch, cli.json_pretty) {
+                    eprintln!("Error explaining branch: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Diff { branch, commit } => {
+                if let Err(e) = diff::diff_repo(&branch, commit) {
+                    eprintln!("Error diffing repository: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Gc { dry_run, yes } => {
+                if let Err(e) = gc::gc_repos(dry_run, yes) {
+                    eprintln!("Error cleaning up repositories: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Switch { branch } => {
+                if let Err(e) = switch::switch_repo(branch.as_deref()) {
+                    eprintln!("Error switching repository: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Focus { branch } => {
+                if let Err(e) = switch::focus_repo(&branch) {
+                    eprintln!("Error focusing repository: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Rename {
+                old_branch,
+                new_branch,
+                force,
+            } => {
+                if let Err(e) = rename::rename_repo(&old_branch, &new_branch, force) {
+                    eprintln!("Error renaming repository: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Info { branch } => {
+                if let Err(e) = info::print_info(branch.as_deref()) {
+                    eprintln!("Error showing repository info: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Stats { json } => {
+                if let Err(e) = stats::print_stats(json, cli.json_pretty) {
+                    eprintln!("Error computing statistics: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Prune { dry_run } => {
+                if let Err(e) = prune::prune_repos(dry_run) {
+                    eprintln!("Error pruning repositories: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Restore { tmux } => {
+                if let Err(e) = restore::restore_repo(tmux) {
+                    eprintln!("Error restoring repository: {e}");
+                    std::process::exit(1);
+                }
+            }
+            Commands::Completions { shell } => {
+                clap_complete::generate(shell, &mut Cli::command(), "trr", &mut std::io::stdout());
+            }
         },
         None => {
             eprintln!("No command specified. Use --help for usage information.");
@@ -89,3 +766,33 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_positionals(argv: &[&str]) -> (String, Vec<String>) {
+        match Cli::try_parse_from(argv).unwrap().command {
+            Some(Commands::Create {
+                branch,
+                args,
+                from_pr,
+                ..
+            }) => split_create_positionals(branch, args, from_pr),
+            _ => panic!("expected the create command"),
+        }
+    }
+
+    #[test]
+    fn test_create_from_pr_with_args() {
+        assert_eq!(
+            create_positionals(&["trr", "create", "--from-pr", "12", "--", "review this"]),
+            (String::new(), vec!["review this".to_string()])
+        );
+        assert_eq!(
+            create_positionals(&["trr", "create", "feature/api", "--", "review this"]),
+            ("feature/api".to_string(), vec!["review this".to_string()])
+        );
+        assert!(Cli::try_parse_from(["trr", "create"]).is_err());
+    }
+}
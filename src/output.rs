@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+// JSON on stdout is compact so it pipes cleanly; --json-pretty is for reading
+pub fn to_json<T: Serialize>(value: &T, pretty: bool) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+// Compact like `du -h`: 512B, 12K, 1.2G
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = UNITS[0];
+    for next_unit in UNITS {
+        value /= 1024.0;
+        unit = next_unit;
+        if value < 1024.0 {
+            break;
+        }
+    }
+    if value < 10.0 {
+        format!("{value:.1}{unit}")
+    } else {
+        format!("{value:.0}{unit}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(512), "512B");
+        assert_eq!(human_size(12 * 1024), "12K");
+        assert_eq!(human_size(340 * 1024 * 1024), "340M");
+        assert_eq!(human_size(1288490189), "1.2G");
+    }
+
+    #[test]
+    fn test_to_json_compact_and_pretty() {
+        let value = serde_json::json!({"branch": "feature/a"});
+        assert_eq!(to_json(&value, false).unwrap(), r#"{"branch":"feature/a"}"#);
+        assert_eq!(
+            to_json(&value, true).unwrap(),
+            "{\n  \"branch\": \"feature/a\"\n}"
+        );
+    }
+}
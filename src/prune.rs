@@ -0,0 +1,76 @@
+use crate::config;
+use crate::delete::{Repository, get_repositories};
+use std::fs;
+use std::path::Path;
+
+// Branch-only copies never have a directory, so they are never stale
+pub fn find_stale<'a>(
+    repositories: &'a [Repository],
+    repo_sync_path: &Path,
+) -> Vec<&'a Repository> {
+    repositories
+        .iter()
+        .filter(|repo| !repo.metadata.branch_only && !repo_sync_path.join(&repo.directory).exists())
+        .collect()
+}
+
+pub fn prune_repos(dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+    let stale = find_stale(&repositories, Path::new(&config.settings.repo_sync_path));
+
+    if stale.is_empty() {
+        println!("No stale repositories found.");
+        return Ok(());
+    }
+
+    for repo in stale {
+        if dry_run {
+            println!("Would prune {}\t{}", repo.ulid, repo.metadata.branch);
+        } else {
+            fs::remove_file(&repo.path)?;
+            println!("Pruned {}\t{}", repo.ulid, repo.metadata.branch);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create::RepositoryMetadata;
+    use ulid::Ulid;
+
+    fn repository(branch: &str, branch_only: bool) -> Repository {
+        Repository {
+            directory: branch.replace('/', "-"),
+            metadata: RepositoryMetadata {
+                branch: branch.to_string(),
+                branch_only,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_stale() {
+        let test_id = Ulid::new().to_string();
+        let base_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(base_dir.join("feature-a")).unwrap();
+
+        let repositories = vec![
+            repository("feature/a", false),
+            repository("feature/b", false),
+            repository("feature/c", true),
+        ];
+
+        let stale = find_stale(&repositories, &base_dir);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].metadata.branch, "feature/b");
+
+        // Clean up
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+}
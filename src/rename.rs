@@ -0,0 +1,264 @@
+use crate::config::{self, Config};
+use crate::create::{
+    branch_to_directory_name, metadata_file_path, read_ulid_metadata, sanitize_tmux_name,
+    tmux_target_name, validate_branch_name,
+};
+use crate::delete::{
+    Repository, find_repository, find_tmux_session_or_window, get_repositories,
+    remove_empty_parents,
+};
+use crate::git::get_repo_prefix;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// A dir prefix (`--dir-prefix`, dir_prefix_template) is kept in front of the new name,
+// and a copy created with --no-sanitize-dir stays nested under the new branch's path
+fn renamed_directory(directory: &str, old_branch: &str, new_branch: &str) -> String {
+    if let Some(prefix) = directory.strip_suffix(&branch_to_directory_name(old_branch)) {
+        return format!("{prefix}{}", branch_to_directory_name(new_branch));
+    }
+    match directory.strip_suffix(old_branch) {
+        Some(prefix) => format!("{prefix}{new_branch}"),
+        None => branch_to_directory_name(new_branch),
+    }
+}
+
+// Windows in repo-session mode are named after the branch alone
+fn renamed_window(target: &str, old_branch: &str, new_name: &str, new_branch: &str) -> String {
+    let window = target.split_once(':').map_or(target, |(_, window)| window);
+    if window == sanitize_tmux_name(old_branch) || window == old_branch {
+        sanitize_tmux_name(new_branch)
+    } else {
+        new_name.to_string()
+    }
+}
+
+// A worktree is registered in the source's .git, so git has to move it
+fn move_copy(worktree: bool, from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if !worktree {
+        return Ok(fs::rename(from, to)?);
+    }
+
+    let output = Command::new("git")
+        .arg("worktree")
+        .arg("move")
+        .arg(from)
+        .arg(to)
+        .output()?;
+    if !output.status.success() {
+        eprintln!(
+            "Failed to move worktree. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err("Failed to move worktree".into());
+    }
+    Ok(())
+}
+
+fn rename_git_branch(
+    dir: &Path,
+    old_branch: &str,
+    new_branch: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("branch")
+        .arg("-m")
+        .arg(old_branch)
+        .arg(new_branch)
+        .output()?;
+    if !output.status.success() {
+        eprintln!(
+            "Failed to rename git branch. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err("Failed to rename git branch".into());
+    }
+    Ok(())
+}
+
+fn rename_tmux(kind: &str, target: &str, new_name: &str) {
+    println!("Renaming tmux {kind} '{target}' to '{new_name}'");
+    let result = Command::new("tmux")
+        .arg(format!("rename-{kind}"))
+        .arg("-t")
+        .arg(target)
+        .arg(new_name)
+        .output();
+    match result {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => eprintln!(
+            "Warning: failed to rename tmux {kind}. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(e) => eprintln!("Warning: failed to rename tmux {kind}: {e}"),
+    }
+}
+
+// The copy is already renamed by now, so a tmux failure only warns
+fn rename_tmux_target(config: &Config, repo: &Repository, new_branch: &str) {
+    let repo_prefix = get_repo_prefix(config);
+    let separator = &config.settings.name_separator;
+    let old_name = tmux_target_name(&repo_prefix, separator, &repo.metadata.branch);
+    let new_name = tmux_target_name(&repo_prefix, separator, new_branch);
+
+    if repo.metadata.session_group.is_some() {
+        // The copy's window is named after its session and linked into the whole group
+        rename_tmux("window", &format!("{old_name}:{old_name}"), &new_name);
+        rename_tmux("session", &old_name, &new_name);
+    } else if let Some((target, is_window)) =
+        find_tmux_session_or_window(config, &repo.metadata.branch, repo.metadata.tmux_kind)
+    {
+        if is_window {
+            let window = renamed_window(&target, &repo.metadata.branch, &new_name, new_branch);
+            rename_tmux("window", &target, &window);
+        } else {
+            rename_tmux("session", &target, &new_name);
+        }
+    }
+}
+
+fn rewrite_metadata(
+    config: &Config,
+    repo: &Repository,
+    new_branch: &str,
+    new_directory: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut metadata = read_ulid_metadata(&repo.path)?;
+    metadata.branch = new_branch.to_string();
+    metadata.directory = Some(new_directory.to_string());
+
+    // metadata_filename may contain {directory}, which moves the file too
+    let new_path = metadata_file_path(config, &repo.ulid, new_directory)?;
+    fs::write(&new_path, serde_json::to_string_pretty(&metadata)?)?;
+    if new_path != repo.path {
+        fs::remove_file(&repo.path)?;
+    }
+    Ok(())
+}
+
+pub fn rename_repo(
+    old_branch: &str,
+    new_branch: &str,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+    let Some(repo) = find_repository(&repositories, Some(old_branch))? else {
+        return Ok(());
+    };
+
+    if !force {
+        validate_branch_name(new_branch)?;
+    }
+    if repositories
+        .iter()
+        .any(|other| other.metadata.branch == new_branch)
+    {
+        return Err(format!("A copy of branch '{new_branch}' already exists").into());
+    }
+
+    let current_dir = std::env::current_dir()?;
+    // Branch-only copies live in the source repository, so only the branch is renamed
+    if repo.metadata.branch_only {
+        rename_git_branch(&current_dir, &repo.metadata.branch, new_branch)?;
+        rewrite_metadata(&config, &repo, new_branch, &repo.directory)?;
+        println!(
+            "Renamed branch '{}' to '{new_branch}'",
+            repo.metadata.branch
+        );
+        return Ok(());
+    }
+
+    let repo_sync_path = PathBuf::from(&config.settings.repo_sync_path);
+    let old_dir = repo_sync_path.join(&repo.directory);
+    let new_directory = renamed_directory(&repo.directory, &repo.metadata.branch, new_branch);
+    let new_dir = repo_sync_path.join(&new_directory);
+    if !old_dir.exists() {
+        return Err(format!("Copy directory '{}' does not exist", old_dir.display()).into());
+    }
+    if new_directory != repo.directory && new_dir.exists() {
+        return Err(format!("Target directory '{}' already exists", new_dir.display()).into());
+    }
+
+    if new_directory != repo.directory {
+        println!(
+            "Moving directory: {} -> {}",
+            old_dir.display(),
+            new_dir.display()
+        );
+        if let Some(parent) = new_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_copy(repo.metadata.worktree, &old_dir, &new_dir)?;
+        remove_empty_parents(&old_dir, &repo_sync_path);
+    }
+
+    println!("Renaming branch: {} -> {new_branch}", repo.metadata.branch);
+    if let Err(e) = rename_git_branch(&new_dir, &repo.metadata.branch, new_branch) {
+        // Put the directory back so the copy still matches its metadata
+        if new_directory != repo.directory {
+            let _ = move_copy(repo.metadata.worktree, &new_dir, &old_dir);
+        }
+        return Err(e);
+    }
+
+    rewrite_metadata(&config, &repo, new_branch, &new_directory)?;
+    rename_tmux_target(&config, &repo, new_branch);
+
+    println!(
+        "Successfully renamed repository '{}' to '{new_branch}'",
+        repo.metadata.branch
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renamed_directory() {
+        assert_eq!(
+            renamed_directory("feature-tset", "feature/tset", "feature/test"),
+            "feature-test"
+        );
+        assert_eq!(
+            renamed_directory("20240101-feature-tset", "feature/tset", "feature/test"),
+            "20240101-feature-test"
+        );
+        // Copies created with --no-sanitize-dir stay nested
+        assert_eq!(
+            renamed_directory("feature/tset", "feature/tset", "fix/tset"),
+            "fix/tset"
+        );
+        assert_eq!(
+            renamed_directory("20240101-feature/tset", "feature/tset", "feature/test"),
+            "20240101-feature/test"
+        );
+    }
+
+    #[test]
+    fn test_renamed_window() {
+        assert_eq!(
+            renamed_window(
+                "trr:feature-tset",
+                "feature/tset",
+                "trr-feature-test",
+                "feature/test"
+            ),
+            "feature-test"
+        );
+        assert_eq!(
+            renamed_window(
+                "main:trr-feature-tset",
+                "feature/tset",
+                "trr-feature-test",
+                "feature/test"
+            ),
+            "trr-feature-test"
+        );
+    }
+}
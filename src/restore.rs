@@ -0,0 +1,251 @@
+use crate::config::{self, Config, expand_tilde};
+use crate::create::{RepositoryMetadata, generate_id, open_tmux_session, write_metadata};
+use crate::git::{current_branch, get_source_repo_name};
+use chrono::Utc;
+use skim::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use ulid::Ulid;
+
+fn list_archives(archive_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if !archive_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut archives: Vec<PathBuf> = fs::read_dir(archive_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with(".tar.gz") || name.ends_with(".tgz"))
+        })
+        .collect();
+    archives.sort();
+    Ok(archives)
+}
+
+fn select_archive_with_skim(archives: &[PathBuf]) -> Option<PathBuf> {
+    let options = SkimOptionsBuilder::default()
+        .height("50%".to_string())
+        .prompt("Select archive> ".to_string())
+        .layout("reverse".to_string())
+        .build()
+        .unwrap();
+
+    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+    for archive in archives {
+        let name = archive.file_name()?.to_string_lossy().to_string();
+        let _ = tx.send(Arc::new(name) as Arc<dyn SkimItem>);
+    }
+    drop(tx);
+
+    let output = Skim::run_with(&options, Some(rx))?;
+    if output.is_abort {
+        return None;
+    }
+
+    let selected = output.selected_items.first()?.output().to_string();
+    archives
+        .iter()
+        .find(|archive| archive.file_name().is_some_and(|name| *name == *selected))
+        .cloned()
+}
+
+// An archive holds exactly one copy, so every entry must share a single
+// top-level directory
+fn archive_root(listing: &str) -> Option<String> {
+    let mut root: Option<&str> = None;
+    for entry in listing.lines() {
+        let entry = entry.trim_start_matches("./");
+        let Some(first) = entry.split('/').next().filter(|first| !first.is_empty()) else {
+            continue;
+        };
+        if first == ".." {
+            return None;
+        }
+        match root {
+            Some(existing) if existing != first => return None,
+            _ => root = Some(first),
+        }
+    }
+    root.map(str::to_string)
+}
+
+fn verify_archive(archive: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    // Listing reads the whole stream, so truncated or corrupt archives fail here
+    let output = Command::new("tar").arg("-tzf").arg(archive).output()?;
+
+    if !output.status.success() {
+        eprintln!(
+            "Failed to read archive. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err(format!("Archive '{}' is corrupt", archive.display()).into());
+    }
+
+    archive_root(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        format!(
+            "Archive '{}' must contain a single top-level directory",
+            archive.display()
+        )
+        .into()
+    })
+}
+
+fn available_directory_name(repo_sync_path: &Path, directory: &str) -> String {
+    if !repo_sync_path.join(directory).exists() {
+        return directory.to_string();
+    }
+
+    (1..)
+        .map(|n| {
+            if n == 1 {
+                format!("{directory}-restored")
+            } else {
+                format!("{directory}-restored-{n}")
+            }
+        })
+        .find(|candidate| !repo_sync_path.join(candidate).exists())
+        .unwrap()
+}
+
+fn extract_archive(
+    config: &Config,
+    archive: &Path,
+    root: &str,
+    directory: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let repo_sync_path = PathBuf::from(&config.settings.repo_sync_path);
+    // Extract into a staging directory first so a failed extraction never
+    // leaves a half-restored copy under its final name
+    let staging_dir = repo_sync_path.join(format!(".trr-restore-{}", Ulid::new()));
+    fs::create_dir_all(&staging_dir)?;
+
+    let extract_result = Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(&staging_dir)
+        .output()?;
+
+    if !extract_result.status.success() {
+        let _ = fs::remove_dir_all(&staging_dir);
+        eprintln!(
+            "Failed to extract archive. stderr: {}",
+            String::from_utf8_lossy(&extract_result.stderr)
+        );
+        return Err("Failed to extract archive".into());
+    }
+
+    let target_dir = repo_sync_path.join(directory);
+    fs::rename(staging_dir.join(root), &target_dir)?;
+    fs::remove_dir_all(&staging_dir)?;
+    Ok(target_dir)
+}
+
+pub fn restore_repo(tmux: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+
+    let archive_dir = config
+        .settings
+        .archive_dir
+        .as_deref()
+        .map(expand_tilde)
+        .ok_or("settings.archive_dir is not configured")?;
+
+    let archives = list_archives(&archive_dir)?;
+    if archives.is_empty() {
+        println!("No archives found in {}", archive_dir.display());
+        return Ok(());
+    }
+
+    let Some(archive) = select_archive_with_skim(&archives) else {
+        println!("No archive selected.");
+        return Ok(());
+    };
+
+    let root = verify_archive(&archive)?;
+    let directory = available_directory_name(Path::new(&config.settings.repo_sync_path), &root);
+    if directory != root {
+        println!("'{root}' already exists, restoring as '{directory}'");
+    }
+
+    let target_dir = extract_archive(&config, &archive, &root, &directory)?;
+    let branch = current_branch(&target_dir).unwrap_or_else(|| directory.clone());
+
+    let ulid = generate_id(config.settings.id_scheme);
+    let metadata = RepositoryMetadata {
+        ulid: Some(ulid.clone()),
+        id_scheme: Some(config.settings.id_scheme),
+        branch: branch.clone(),
+        created_at: Utc::now(),
+        directory: Some(directory),
+        repo_name: Some(get_source_repo_name()),
+        source_path: std::env::current_dir()
+            .ok()
+            .map(|dir| dir.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    write_metadata(&config, &ulid, &metadata)?;
+
+    println!("Repository restored:");
+    println!("  Archive: {}", archive.display());
+    println!("  Branch: {branch}");
+    println!("  Directory: {}", target_dir.display());
+    println!("  ULID: {ulid}");
+
+    if tmux {
+        let absolute_target_dir = std::env::current_dir()?.join(&target_dir);
+        // Init commands are skipped because they could reset the restored working tree
+        open_tmux_session(&config, &branch, &absolute_target_dir, "", &[], None, None)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_root() {
+        assert_eq!(
+            archive_root("feature-a/\nfeature-a/.git/\nfeature-a/src/main.rs\n"),
+            Some("feature-a".to_string())
+        );
+        assert_eq!(
+            archive_root("./feature-a/\n./feature-a/README.md\n"),
+            Some("feature-a".to_string())
+        );
+        assert_eq!(archive_root("feature-a/\nfeature-b/\n"), None);
+        assert_eq!(archive_root("../escape\n"), None);
+        assert_eq!(archive_root(""), None);
+    }
+
+    #[test]
+    fn test_available_directory_name() {
+        let test_id = Ulid::new().to_string();
+        let base_dir = std::env::temp_dir().join(format!("trr_test_{test_id}"));
+        fs::create_dir_all(base_dir.join("feature-a")).unwrap();
+
+        assert_eq!(
+            available_directory_name(&base_dir, "feature-b"),
+            "feature-b"
+        );
+        assert_eq!(
+            available_directory_name(&base_dir, "feature-a"),
+            "feature-a-restored"
+        );
+        fs::create_dir_all(base_dir.join("feature-a-restored")).unwrap();
+        assert_eq!(
+            available_directory_name(&base_dir, "feature-a"),
+            "feature-a-restored-2"
+        );
+
+        // Clean up
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::config;
+use crate::delete::{Repository, get_repositories, load_sizes};
+use crate::output;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const TOP_PREFIX_COUNT: usize = 5;
+const NO_PREFIX: &str = "(none)";
+
+// Upper bounds in days; the last bucket takes everything older
+const AGE_BUCKETS: [(&str, Option<i64>); 4] = [
+    ("< 1 day", Some(1)),
+    ("1-7 days", Some(7)),
+    ("7-30 days", Some(30)),
+    ("> 30 days", None),
+];
+
+#[derive(Debug, Serialize, PartialEq)]
+struct AgeBucket {
+    label: &'static str,
+    count: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct PrefixCount {
+    prefix: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Stats {
+    total_copies: usize,
+    branch_only: usize,
+    total_bytes: u64,
+    average_bytes: u64,
+    median_bytes: u64,
+    ages: Vec<AgeBucket>,
+    branch_prefixes: Vec<PrefixCount>,
+    groups: BTreeMap<String, usize>,
+}
+
+fn median(sizes: &mut [u64]) -> u64 {
+    if sizes.is_empty() {
+        return 0;
+    }
+    sizes.sort_unstable();
+    let middle = sizes.len() / 2;
+    if sizes.len() % 2 == 0 {
+        (sizes[middle - 1] + sizes[middle]) / 2
+    } else {
+        sizes[middle]
+    }
+}
+
+fn branch_prefix(branch: &str) -> &str {
+    branch
+        .split_once('/')
+        .map_or(NO_PREFIX, |(prefix, _)| prefix)
+}
+
+// Branch-only copies have nothing on disk, so they are left out of the size figures
+fn compute_stats(repositories: &[Repository], now: DateTime<Utc>) -> Stats {
+    let mut copy_sizes: Vec<u64> = repositories
+        .iter()
+        .filter(|repo| !repo.metadata.branch_only)
+        .map(|repo| repo.size.unwrap_or_default())
+        .collect();
+    let total_bytes: u64 = copy_sizes.iter().sum();
+    let average_bytes = total_bytes
+        .checked_div(copy_sizes.len() as u64)
+        .unwrap_or_default();
+
+    let mut ages: Vec<AgeBucket> = AGE_BUCKETS
+        .iter()
+        .map(|(label, _)| AgeBucket { label, count: 0 })
+        .collect();
+    for repo in repositories {
+        let age = now - repo.metadata.created_at;
+        let index = AGE_BUCKETS
+            .iter()
+            .position(|(_, days)| days.is_none_or(|days| age < TimeDelta::days(days)))
+            .unwrap_or(AGE_BUCKETS.len() - 1);
+        ages[index].count += 1;
+    }
+
+    let mut prefix_counts: HashMap<&str, usize> = HashMap::new();
+    for repo in repositories {
+        *prefix_counts
+            .entry(branch_prefix(&repo.metadata.branch))
+            .or_default() += 1;
+    }
+    let mut branch_prefixes: Vec<PrefixCount> = prefix_counts
+        .into_iter()
+        .map(|(prefix, count)| PrefixCount {
+            prefix: prefix.to_string(),
+            count,
+        })
+        .collect();
+    branch_prefixes.sort_by(|a, b| b.count.cmp(&a.count).then(a.prefix.cmp(&b.prefix)));
+    branch_prefixes.truncate(TOP_PREFIX_COUNT);
+
+    let mut groups = BTreeMap::new();
+    for group in repositories
+        .iter()
+        .filter_map(|repo| repo.metadata.group.as_ref())
+    {
+        *groups.entry(group.clone()).or_default() += 1;
+    }
+
+    Stats {
+        total_copies: repositories.len(),
+        branch_only: repositories
+            .iter()
+            .filter(|repo| repo.metadata.branch_only)
+            .count(),
+        total_bytes,
+        average_bytes,
+        median_bytes: median(&mut copy_sizes),
+        ages,
+        branch_prefixes,
+        groups,
+    }
+}
+
+fn format_stats(stats: &Stats) -> Vec<String> {
+    let mb = |bytes: u64| bytes / (1024 * 1024);
+    let mut lines = vec![
+        format!(
+            "Copies: {} ({} branch only)",
+            stats.total_copies, stats.branch_only
+        ),
+        format!(
+            "Disk usage: {} MB total, {} MB average, {} MB median",
+            mb(stats.total_bytes),
+            mb(stats.average_bytes),
+            mb(stats.median_bytes)
+        ),
+        "Age:".to_string(),
+    ];
+    for bucket in &stats.ages {
+        lines.push(format!("  {:<10} {}", bucket.label, bucket.count));
+    }
+
+    lines.push("Branch prefixes:".to_string());
+    for prefix in &stats.branch_prefixes {
+        lines.push(format!("  {:<10} {}", prefix.prefix, prefix.count));
+    }
+
+    if stats.groups.is_empty() {
+        lines.push("Groups: none".to_string());
+    } else {
+        lines.push("Groups:".to_string());
+        for (group, count) in &stats.groups {
+            lines.push(format!("  {group:<10} {count}"));
+        }
+    }
+    lines
+}
+
+pub fn print_stats(json: bool, json_pretty: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let mut repositories = get_repositories(&config)?;
+    load_sizes(
+        Path::new(&config.settings.repo_sync_path),
+        &mut repositories,
+    );
+    let stats = compute_stats(&repositories, Utc::now());
+
+    if json {
+        println!("{}", output::to_json(&stats, json_pretty)?);
+        return Ok(());
+    }
+
+    if repositories.is_empty() {
+        println!("No repository copies found");
+        return Ok(());
+    }
+
+    for line in format_stats(&stats) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create::RepositoryMetadata;
+
+    fn repository(branch: &str, days_old: i64, now: DateTime<Utc>) -> Repository {
+        Repository {
+            directory: branch.replace('/', "-"),
+            metadata: RepositoryMetadata {
+                branch: branch.to_string(),
+                created_at: now - TimeDelta::days(days_old),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let now = Utc::now();
+        let mut repositories = vec![
+            repository("feature/a", 0, now),
+            repository("feature/b", 3, now),
+            repository("fix/c", 10, now),
+            repository("main-copy", 40, now),
+            repository("feature/d", 2, now),
+        ];
+        repositories[0].metadata.group = Some("epic-42".to_string());
+        repositories[1].metadata.group = Some("epic-42".to_string());
+        repositories[4].metadata.branch_only = true;
+        for (repo, size) in repositories.iter_mut().zip([100, 300, 200, 1000]) {
+            repo.size = Some(size);
+        }
+
+        let stats = compute_stats(&repositories, now);
+        assert_eq!(stats.total_copies, 5);
+        assert_eq!(stats.branch_only, 1);
+        assert_eq!(stats.total_bytes, 1600);
+        assert_eq!(stats.average_bytes, 400);
+        assert_eq!(stats.median_bytes, 250);
+        let counts: Vec<_> = stats.ages.iter().map(|bucket| bucket.count).collect();
+        assert_eq!(counts, vec![1, 2, 1, 1]);
+        assert_eq!(
+            stats.branch_prefixes[0],
+            PrefixCount {
+                prefix: "feature".to_string(),
+                count: 3
+            }
+        );
+        assert_eq!(stats.branch_prefixes.len(), 3);
+        assert_eq!(stats.groups["epic-42"], 2);
+
+        let empty = compute_stats(&[], now);
+        assert_eq!(empty.average_bytes, 0);
+        assert_eq!(empty.median_bytes, 0);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::config;
+use crate::create::{CreateOptions, open_tmux_session, render_init_commands};
+use crate::delete::{Repository, find_repository, find_tmux_session_or_window, get_repositories};
+use std::path::PathBuf;
+use std::process::Command;
+
+// The init command flags create was given; the config settings apply on their own
+fn recreate_options(repo: &Repository) -> CreateOptions {
+    CreateOptions {
+        template_engine: repo.metadata.template_engine,
+        quiet_tmux: repo.metadata.quiet_tmux,
+        ..Default::default()
+    }
+}
+
+pub fn attach(name: &str, is_window: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let in_tmux = std::env::var("TMUX").is_ok();
+    // switch-client also accepts session:window targets of repo-session mode
+    let command = if in_tmux {
+        "switch-client"
+    } else {
+        "attach-session"
+    };
+
+    println!(
+        "Switching to tmux {} '{name}'...",
+        if is_window { "window" } else { "session" }
+    );
+    let status = Command::new("tmux")
+        .arg(command)
+        .arg("-t")
+        .arg(name)
+        .status()?;
+
+    if !status.success() {
+        return Err(format!("tmux {command} failed").into());
+    }
+    Ok(())
+}
+
+fn current_session() -> Option<String> {
+    let output = Command::new("tmux")
+        .arg("display-message")
+        .arg("-p")
+        .arg("#{session_name}")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// select-window only changes the window a session shows, so a window in another
+// session needs switch-client to move the client there
+fn focus_command(target: &str, current_session: Option<&str>) -> &'static str {
+    match (target.split_once(':'), current_session) {
+        (Some((session, _)), Some(current)) if session != current => "switch-client",
+        _ => "select-window",
+    }
+}
+
+// Unlike switch, this never recreates anything, so a branch without metadata is still
+// focused by searching every tmux kind
+pub fn focus_repo(branch: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let recorded = get_repositories(&config)?
+        .into_iter()
+        .find(|repo| repo.metadata.branch == branch)
+        .and_then(|repo| repo.metadata.tmux_kind);
+    let Some((name, is_window)) = find_tmux_session_or_window(&config, branch, recorded) else {
+        return Err(format!("No tmux session or window found for '{branch}'").into());
+    };
+
+    if !is_window || std::env::var("TMUX").is_err() {
+        return attach(&name, is_window);
+    }
+
+    let command = focus_command(&name, current_session().as_deref());
+    let status = Command::new("tmux")
+        .arg(command)
+        .arg("-t")
+        .arg(&name)
+        .status()?;
+    if !status.success() {
+        return Err(format!("tmux {command} failed").into());
+    }
+    Ok(())
+}
+
+pub fn switch_repo(branch: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+
+    let Some(repo) = find_repository(&repositories, branch)? else {
+        println!("No repository selected.");
+        return Ok(());
+    };
+
+    if repo.metadata.branch_only {
+        return Err(format!(
+            "'{}' was created with --branch-only and has no tmux session",
+            repo.metadata.branch
+        )
+        .into());
+    }
+
+    if let Some((name, is_window)) =
+        find_tmux_session_or_window(&config, &repo.metadata.branch, repo.metadata.tmux_kind)
+    {
+        return attach(&name, is_window);
+    }
+
+    let copy_dir = std::env::current_dir()?
+        .join(PathBuf::from(&config.settings.repo_sync_path))
+        .join(&repo.directory);
+    if !copy_dir.exists() {
+        return Err(format!("Directory '{}' does not exist", copy_dir.display()).into());
+    }
+
+    // Recreate it the way create did, with the args recorded at creation time
+    let init_commands = render_init_commands(
+        &config,
+        &recreate_options(&repo),
+        &repo.metadata.args,
+        repo.metadata.alias.as_deref(),
+        &repo.metadata.branch,
+        &copy_dir,
+    )?;
+    open_tmux_session(
+        &config,
+        &repo.metadata.branch,
+        &copy_dir,
+        &init_commands,
+        &repo.metadata.args,
+        repo.metadata.label.as_deref(),
+        repo.metadata.session_group.as_deref(),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::create::RepositoryMetadata;
+    use std::path::Path;
+
+    #[test]
+    fn test_focus_command() {
+        assert_eq!(
+            focus_command("main:trr-feature-x", Some("main")),
+            "select-window"
+        );
+        assert_eq!(
+            focus_command("work:trr-feature-x", Some("main")),
+            "switch-client"
+        );
+        assert_eq!(focus_command("work:trr-feature-x", None), "select-window");
+    }
+
+    #[test]
+    fn test_recreate_options() {
+        let mut config = Config::default();
+        config.settings.tmux_window_init_commands = "echo {{branch}}".to_string();
+        let mut repo = Repository {
+            metadata: RepositoryMetadata {
+                branch: "feature/test".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let render = |repo: &Repository| {
+            render_init_commands(
+                &config,
+                &recreate_options(repo),
+                &repo.metadata.args,
+                None,
+                &repo.metadata.branch,
+                Path::new("/tmp/copy"),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(render(&repo), "echo {{branch}}");
+        repo.metadata.template_engine = true;
+        repo.metadata.quiet_tmux = true;
+        assert_eq!(render(&repo), " echo feature/test");
+    }
+}
@@ -0,0 +1,95 @@
+use crate::config::{self, Config};
+use crate::create::{CreateOptions, build_rsync_command, confirm, git_status_porcelain};
+use crate::delete::{find_repository, get_repositories};
+use std::path::PathBuf;
+use std::process::Command;
+
+pub fn build_sync_command(config: &Config, linked_paths: &[String]) -> Command {
+    let mut rsync_command = build_rsync_command(config, &CreateOptions::default());
+    // Excluded paths are protected from --delete, so the copy keeps its own
+    // .git (and branch) and the symlinks created by --link-config
+    rsync_command.arg("--delete").arg("--exclude").arg(".git");
+    for linked_path in linked_paths {
+        rsync_command
+            .arg("--exclude")
+            .arg(format!("/{linked_path}"));
+    }
+    rsync_command
+}
+
+pub fn sync_repo(
+    branch: Option<&str>,
+    dry_run: bool,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::load()?;
+    let repositories = get_repositories(&config)?;
+
+    let Some(repo) = find_repository(&repositories, branch)? else {
+        println!("No repository selected.");
+        return Ok(());
+    };
+
+    if repo.metadata.branch_only {
+        return Err(format!(
+            "'{}' was created with --branch-only and has no copy",
+            repo.metadata.branch
+        )
+        .into());
+    }
+
+    let source_path = repo.metadata.source_path.as_deref().ok_or_else(|| {
+        format!(
+            "No source path recorded for '{}'. Recreate the copy to enable sync.",
+            repo.metadata.branch
+        )
+    })?;
+
+    let copy_dir = std::env::current_dir()?
+        .join(PathBuf::from(&config.settings.repo_sync_path))
+        .join(&repo.directory);
+    if !copy_dir.exists() {
+        return Err(format!("Directory '{}' does not exist", copy_dir.display()).into());
+    }
+
+    let mut rsync_command = build_sync_command(&config, &repo.metadata.linked_paths);
+
+    if dry_run {
+        rsync_command.arg("-n").arg("--out-format=%n");
+    } else {
+        if let Some(status) = git_status_porcelain(&copy_dir, &config.settings.dirty_ignore) {
+            if !status.is_empty() {
+                eprintln!("Warning: the copy has local changes that may be overwritten:");
+                eprint!("{status}");
+            }
+        }
+
+        if !yes
+            && !confirm(&format!(
+                "Sync '{}' from {source_path}? Files missing from the source will be deleted.",
+                repo.metadata.branch
+            ))?
+        {
+            println!("Sync cancelled.");
+            return Ok(());
+        }
+    }
+
+    let rsync_result = rsync_command
+        .arg(format!("{source_path}/"))
+        .arg(format!("{}/", copy_dir.display()))
+        .status()?;
+
+    if !rsync_result.success() {
+        return Err("rsync failed".into());
+    }
+
+    if !dry_run {
+        println!(
+            "Successfully synced '{}' from {source_path}",
+            repo.metadata.branch
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    If {
+        name: String,
+        then_nodes: Vec<Node>,
+        else_nodes: Vec<Node>,
+    },
+}
+
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+fn tokenize(template: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| "Unclosed '{{' in init commands template".to_string())?;
+        tokens.push(Token::Tag(rest[start + 2..start + end].trim().to_string()));
+        rest = &rest[start + end + 2..];
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+fn parse(
+    tokens: &mut std::vec::IntoIter<Token>,
+    in_if: bool,
+) -> Result<(Vec<Node>, Vec<Node>), String> {
+    let mut then_nodes = Vec::new();
+    let mut else_nodes = Vec::new();
+    let mut in_else = false;
+
+    while let Some(token) = tokens.next() {
+        let node = match token {
+            Token::Text(text) => Node::Text(text),
+            Token::Tag(tag) => {
+                if let Some(name) = tag.strip_prefix("#if ") {
+                    let (inner_then, inner_else) = parse(tokens, true)?;
+                    Node::If {
+                        name: name.trim().to_string(),
+                        then_nodes: inner_then,
+                        else_nodes: inner_else,
+                    }
+                } else if tag == "else" && in_if && !in_else {
+                    in_else = true;
+                    continue;
+                } else if tag == "/if" && in_if {
+                    return Ok((then_nodes, else_nodes));
+                } else if tag.starts_with('#') || tag.starts_with('/') || tag == "else" {
+                    return Err(format!(
+                        "Unexpected '{{{{{tag}}}}}' in init commands template"
+                    ));
+                } else {
+                    Node::Var(tag)
+                }
+            }
+        };
+
+        if in_else {
+            else_nodes.push(node);
+        } else {
+            then_nodes.push(node);
+        }
+    }
+
+    if in_if {
+        return Err("Missing '{{/if}}' in init commands template".to_string());
+    }
+    Ok((then_nodes, else_nodes))
+}
+
+fn render_nodes(nodes: &[Node], vars: &HashMap<&str, String>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Var(name) => output.push_str(vars.get(name.as_str()).map_or("", |v| v)),
+            Node::If {
+                name,
+                then_nodes,
+                else_nodes,
+            } => {
+                let truthy = vars.get(name.as_str()).is_some_and(|v| !v.is_empty());
+                render_nodes(if truthy { then_nodes } else { else_nodes }, vars, output);
+            }
+        }
+    }
+}
+
+pub fn render(template: &str, vars: &HashMap<&str, String>) -> Result<String, String> {
+    let (nodes, _) = parse(&mut tokenize(template)?.into_iter(), false)?;
+    let mut output = String::new();
+    render_nodes(&nodes, vars, &mut output);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(args: &str) -> HashMap<&'static str, String> {
+        HashMap::from([
+            ("args", args.to_string()),
+            ("branch", "feature/test".to_string()),
+            ("dir", "/repo/.trr/feature-test".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_render_variables() {
+        let result = render("cd {{dir}} && echo {{ branch }}", &vars("")).unwrap();
+        assert_eq!(result, "cd /repo/.trr/feature-test && echo feature/test");
+    }
+
+    #[test]
+    fn test_render_if_else() {
+        let template = "{{#if args}}claude \"{{args}}\"{{else}}claude{{/if}}";
+        assert_eq!(
+            render(template, &vars("review this")).unwrap(),
+            "claude \"review this\""
+        );
+        assert_eq!(render(template, &vars("")).unwrap(), "claude");
+    }
+
+    #[test]
+    fn test_render_nested_if() {
+        let template = "{{#if branch}}a{{#if args}}b{{/if}}c{{/if}}";
+        assert_eq!(render(template, &vars("")).unwrap(), "ac");
+        assert_eq!(render(template, &vars("x")).unwrap(), "abc");
+    }
+
+    #[test]
+    fn test_render_keeps_legacy_placeholders() {
+        assert_eq!(render("echo @@args", &vars("x")).unwrap(), "echo @@args");
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert!(render("{{#if args}}x", &vars("")).is_err());
+        assert!(render("{{/if}}", &vars("")).is_err());
+        assert!(render("{{args", &vars("")).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::config::Config;
+use crate::sync::build_sync_command;
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+use std::path::{Component, Path};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// Changes under .git or repo_sync_path (which contains the copy itself) must
+// not trigger a re-sync, or every sync would trigger the next one
+fn is_relevant_change(path: &Path, source_dir: &Path, repo_sync_path: &str) -> bool {
+    let Ok(relative_path) = path.strip_prefix(source_dir) else {
+        return false;
+    };
+
+    let ignored = Path::new(repo_sync_path);
+    if relative_path.starts_with(".git") || relative_path.starts_with(ignored) {
+        return false;
+    }
+
+    !matches!(
+        relative_path.components().next(),
+        None | Some(Component::CurDir)
+    )
+}
+
+fn run_sync(config: &Config, source_dir: &Path, copy_dir: &Path, linked_paths: &[String]) {
+    let result = build_sync_command(config, linked_paths)
+        .arg(format!("{}/", source_dir.display()))
+        .arg(format!("{}/", copy_dir.display()))
+        .status();
+
+    match result {
+        Ok(status) if status.success() => {
+            println!(
+                "Synced changes at {}",
+                chrono::Local::now().format("%H:%M:%S")
+            );
+        }
+        Ok(status) => eprintln!("Warning: rsync exited with {status}"),
+        Err(e) => eprintln!("Warning: failed to run rsync: {e}"),
+    }
+}
+
+pub fn watch_and_sync(
+    config: &Config,
+    source_dir: &Path,
+    copy_dir: &Path,
+    linked_paths: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(DEBOUNCE, tx)?;
+    debouncer
+        .watcher()
+        .watch(source_dir, RecursiveMode::Recursive)?;
+
+    println!(
+        "Watching {} for changes. Press Ctrl-C to stop.",
+        source_dir.display()
+    );
+
+    while running.load(Ordering::SeqCst) {
+        match rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(Ok(events)) => {
+                if events.iter().any(|event| {
+                    is_relevant_change(&event.path, source_dir, &config.settings.repo_sync_path)
+                }) {
+                    run_sync(config, source_dir, copy_dir, linked_paths);
+                }
+            }
+            Ok(Err(e)) => eprintln!("Warning: watch error: {e}"),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    println!("Stopped watching.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_relevant_change() {
+        let source_dir = Path::new("/repo");
+
+        assert!(is_relevant_change(
+            Path::new("/repo/src/main.rs"),
+            source_dir,
+            ".trr"
+        ));
+        assert!(!is_relevant_change(
+            Path::new("/repo/.git/index"),
+            source_dir,
+            ".trr"
+        ));
+        assert!(!is_relevant_change(
+            Path::new("/repo/.trr/feature-x/src/main.rs"),
+            source_dir,
+            ".trr"
+        ));
+        assert!(!is_relevant_change(Path::new("/repo"), source_dir, ".trr"));
+        assert!(!is_relevant_change(
+            Path::new("/elsewhere/file"),
+            source_dir,
+            ".trr"
+        ));
+    }
+}